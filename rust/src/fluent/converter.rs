@@ -0,0 +1,631 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use fluent::{FluentArgs, FluentBundle, FluentResource};
+use fluent_syntax::ast;
+use fluent_syntax::serializer::serialize;
+use godot::classes::{FileAccess, ProjectSettings};
+use godot::global::error_string;
+use godot::global::Error as GdErr;
+use godot::prelude::*;
+use unic_langid::LanguageIdentifier;
+
+use crate::utils::create_file_for_write;
+
+use super::locale::compute_locale;
+use super::pattern::text_to_pattern;
+use super::project_settings::{INVALID_MESSAGE_HANDLING_SKIP, PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING};
+use super::FluentGenerator;
+
+/// CLDR plural categories, in the order that gettext plural forms usually follow.
+const PLURAL_CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// Converts translation files of other formats into Fluent Translation List (FTL) files.
+///
+/// Supports gettext `.po` and `.pot` files as well as Godot's translation CSV format.
+/// Existing FTL files at the target path are overwritten.
+#[derive(GodotClass)]
+#[class(no_init)]
+pub struct FluentConverter {
+    /// How the `msgctxt` of gettext entries is converted.
+    /// Either [constant CONTEXT_AS_ATTRIBUTE] or [constant CONTEXT_AS_ID_PREFIX].
+    #[var]
+    context_handling: i32,
+    /// Name of the variable which selects the plural form of a message, e.g. `count` results in `{ $count -> ... }`.
+    #[var]
+    plural_variable: GString,
+    /// If a message identifier is invalid (e.g. contains symbols or spaces), should it be skipped or should the invalid symbols be replaced with underscores?
+    /// Defaults to the `internationalization/fluent/generator/invalid_message_handling` Project Setting.
+    #[var]
+    invalid_message_handling: i32,
+}
+
+/// A single entry of a gettext file.
+#[derive(Default)]
+struct PoEntry {
+    comments: Vec<String>,
+    fuzzy: bool,
+    context: Option<String>,
+    id: String,
+    id_plural: Option<String>,
+    strs: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum PoField {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+#[godot_api]
+impl FluentConverter {
+    /// Context is converted to an attribute of the message, which can be retrieved using the context parameter of [method Object.tr].
+    #[constant]
+    const CONTEXT_AS_ATTRIBUTE: i32 = 0;
+    /// Context is prepended to the message identifier, separated by a `-`.
+    #[constant]
+    const CONTEXT_AS_ID_PREFIX: i32 = 1;
+
+    /// Create a new [FluentConverter] instance using the Project Settings for configuration.
+    #[func]
+    pub fn create() -> Gd<Self> {
+        let project_settings = ProjectSettings::singleton();
+        Gd::from_object(Self {
+            context_handling: Self::CONTEXT_AS_ATTRIBUTE,
+            plural_variable: "count".into(),
+            invalid_message_handling: i32::from_variant(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING)),
+        })
+    }
+
+    /// Convert a gettext `.po` or `.pot` file into a FTL file.
+    ///
+    /// The locale used for plural forms is read from the `Language` header, or otherwise deduced from [param target_path] like the loader does.
+    /// For `.pot` templates, the source text is used as the translation.
+    /// Untranslated and fuzzy entries are skipped.
+    #[func]
+    pub fn convert_po(&self, source_path: GString, target_path: GString) -> GdErr {
+        let text = FileAccess::get_file_as_string(&source_path);
+        let err = FileAccess::get_open_error();
+        if err != GdErr::OK {
+            godot_error!("Unable to read {}: {}", source_path, error_string(err.ord() as i64));
+            return err;
+        }
+
+        let is_template = source_path.to_string().to_lowercase().ends_with(".pot");
+        let entries = Self::parse_po(&text.to_string());
+
+        // Deduce locale, first from header and then from file path.
+        let header = entries.iter().find(|entry| entry.id.is_empty() && entry.context.is_none());
+        let locale = header
+            .and_then(|header| Self::get_po_header(header, "Language"))
+            .filter(|locale| !locale.is_empty())
+            .or_else(|| compute_locale(&PathBuf::from(target_path.to_string())));
+        let plural_categories = Self::get_plural_categories(locale.as_deref(), is_template);
+
+        let mut builder = MessageBuilder::new(self.invalid_message_handling);
+        for entry in entries {
+            if entry.id.is_empty() || (entry.fuzzy && !is_template) {
+                continue;
+            }
+
+            let forms = if is_template {
+                let mut forms = vec![entry.id.clone()];
+                forms.extend(entry.id_plural.clone());
+                forms
+            } else {
+                entry.strs.clone()
+            };
+            if forms.iter().all(|form| form.is_empty()) {
+                // Untranslated.
+                continue;
+            }
+
+            let pattern = if entry.id_plural.is_some() {
+                self.plural_pattern(&forms, &plural_categories, is_template)
+            } else {
+                text_to_pattern(&forms[0])
+            };
+
+            let context = entry.context.filter(|context| !context.is_empty());
+            match context {
+                Some(context) if self.context_handling == Self::CONTEXT_AS_ATTRIBUTE => {
+                    builder.add_attribute(&entry.id, &context, pattern, entry.comments);
+                },
+                Some(context) => {
+                    builder.add_value(&format!("{}-{}", context, entry.id), pattern, entry.comments);
+                },
+                None => {
+                    builder.add_value(&entry.id, pattern, entry.comments);
+                },
+            }
+        }
+
+        builder.write(&target_path)
+    }
+
+    /// Convert a Godot translation CSV file into FTL files, one per locale column.
+    ///
+    /// [param target_path] should contain the placeholder `{$locale}`, which is replaced with the locale of each column.
+    /// Columns starting with an underscore are ignored, same as in Godot's CSV importer.
+    #[func]
+    pub fn convert_csv(&self, source_path: GString, target_path: GString) -> GdErr {
+        let text = FileAccess::get_file_as_string(&source_path);
+        let err = FileAccess::get_open_error();
+        if err != GdErr::OK {
+            godot_error!("Unable to read {}: {}", source_path, error_string(err.ord() as i64));
+            return err;
+        }
+
+        let mut rows = Self::parse_csv(&text.to_string()).into_iter();
+        let header = rows.next().unwrap_or_default();
+        let locales = header
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, locale)| !locale.is_empty() && !locale.starts_with('_'))
+            .map(|(column, locale)| (column, locale.clone()))
+            .collect::<Vec<_>>();
+
+        let target_path = target_path.to_string();
+        if locales.len() > 1 && !target_path.contains("{$locale}") {
+            godot_error!("{} contains multiple locales, but target path {} does not contain the {{$locale}} placeholder.", source_path, target_path);
+            return GdErr::ERR_INVALID_PARAMETER;
+        }
+
+        let mut builders = locales
+            .iter()
+            .map(|_| MessageBuilder::new(self.invalid_message_handling))
+            .collect::<Vec<_>>();
+        for line in rows {
+            let Some(key) = line.first() else {
+                continue;
+            };
+            if key.is_empty() {
+                continue;
+            }
+
+            for ((column, _), builder) in locales.iter().zip(builders.iter_mut()) {
+                let value = line.get(*column).cloned().unwrap_or_default();
+                if value.is_empty() {
+                    // Untranslated.
+                    continue;
+                }
+                builder.add_value(key, text_to_pattern(&value), vec![]);
+            }
+        }
+
+        let mut result = GdErr::OK;
+        for ((_, locale), builder) in locales.iter().zip(builders) {
+            let target = GString::from(target_path.replace("{$locale}", locale));
+            let err = builder.write(&target);
+            if err != GdErr::OK {
+                result = err;
+            }
+        }
+        result
+    }
+
+    fn plural_pattern(&self, forms: &[String], categories: &[String], is_template: bool) -> ast::Pattern<String> {
+        // Templates only contain the singular and plural source text.
+        let categories = if is_template {
+            vec!["one".to_owned(), "other".to_owned()]
+        } else {
+            categories.to_vec()
+        };
+        if forms.len() != categories.len() {
+            godot_warn!(
+                "Expected {} plural forms ({}), but got {}. Plural forms may be assigned to the wrong category.",
+                categories.len(), categories.join(", "), forms.len()
+            );
+        }
+
+        let last_index = forms.len() - 1;
+        let variants = forms
+            .iter()
+            .enumerate()
+            .filter_map(|(index, form)| {
+                // The last form is always the fallback.
+                let category = if index == last_index {
+                    "other".to_owned()
+                } else {
+                    categories.get(index).filter(|category| category.as_str() != "other")?.clone()
+                };
+                Some(ast::Variant {
+                    key: ast::VariantKey::Identifier { name: category },
+                    value: text_to_pattern(form),
+                    default: index == last_index,
+                })
+            })
+            .collect();
+
+        ast::Pattern {
+            elements: vec![ast::PatternElement::Placeable {
+                expression: ast::Expression::Select {
+                    selector: ast::InlineExpression::VariableReference {
+                        id: ast::Identifier { name: self.plural_variable.to_string() },
+                    },
+                    variants,
+                },
+            }],
+        }
+    }
+
+    /// Returns the plural categories used by integers in the given locale, in gettext order.
+    fn get_plural_categories(locale: Option<&str>, is_template: bool) -> Vec<String> {
+        let fallback = vec!["one".to_owned(), "other".to_owned()];
+        if is_template {
+            return fallback;
+        }
+
+        let Some(lang_id) = locale.and_then(|locale| locale.parse::<LanguageIdentifier>().ok()) else {
+            godot_warn!("Unable to determine locale of gettext file, assuming plural forms \"one\" and \"other\".");
+            return fallback;
+        };
+
+        // Let Fluent itself decide which category each number belongs to.
+        let mut bundle = FluentBundle::new(vec![lang_id]);
+        bundle.set_use_isolating(false);
+        let variants = PLURAL_CATEGORIES
+            .iter()
+            .map(|category| {
+                let default = if *category == "other" { "*" } else { " " };
+                format!("   {default}[{category}] {category}\n")
+            })
+            .collect::<String>();
+        let res = FluentResource::try_new(format!("category = {{ $n ->\n{variants}}}\n")).unwrap();
+        bundle.add_resource(res).unwrap();
+        let message = bundle.get_message("category").unwrap();
+        let pattern = message.value().unwrap();
+
+        let mut found = HashSet::new();
+        for n in 0..=200 {
+            let mut args = FluentArgs::new();
+            args.set("n", n);
+            let mut errors = vec![];
+            found.insert(bundle.format_pattern(pattern, Some(&args), &mut errors).into_owned());
+        }
+
+        PLURAL_CATEGORIES
+            .iter()
+            .filter(|category| found.contains(**category))
+            .map(|category| category.to_string())
+            .collect()
+    }
+
+    fn get_po_header(header: &PoEntry, key: &str) -> Option<String> {
+        header.strs.first()?.lines().find_map(|line| {
+            let (line_key, value) = line.split_once(':')?;
+            if line_key.trim().eq_ignore_ascii_case(key) {
+                Some(value.trim().to_owned())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn parse_po(text: &str) -> Vec<PoEntry> {
+        let mut entries = vec![];
+        let mut entry = PoEntry::default();
+        let mut field = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // A comment or keyword after a translation starts the next entry.
+            let starts_entry = line.starts_with('#') || line.starts_with("msgctxt") || line.starts_with("msgid ");
+            if starts_entry && !entry.strs.is_empty() {
+                entries.push(std::mem::take(&mut entry));
+                field = None;
+            }
+
+            if let Some(flags) = line.strip_prefix("#,") {
+                entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            } else if line.starts_with("#:") || line.starts_with("#|") || line.starts_with("#~") {
+                // References, previous strings and obsolete entries are not needed.
+            } else if let Some(comment) = line.strip_prefix("#.").or_else(|| line.strip_prefix('#')) {
+                entry.comments.push(comment.trim().to_owned());
+            } else if line.starts_with('"') {
+                let value = Self::unquote(line);
+                match field {
+                    Some(PoField::Context) => entry.context.get_or_insert_with(String::new).push_str(&value),
+                    Some(PoField::Id) => entry.id.push_str(&value),
+                    Some(PoField::IdPlural) => entry.id_plural.get_or_insert_with(String::new).push_str(&value),
+                    Some(PoField::Str(index)) => entry.strs[index].push_str(&value),
+                    None => godot_warn!("Unexpected string in gettext file: {}", line),
+                }
+            } else {
+                let (keyword, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                let value = Self::unquote(value.trim());
+                field = match keyword {
+                    "msgctxt" => {
+                        entry.context = Some(value);
+                        Some(PoField::Context)
+                    },
+                    "msgid" => {
+                        entry.id = value;
+                        Some(PoField::Id)
+                    },
+                    "msgid_plural" => {
+                        entry.id_plural = Some(value);
+                        Some(PoField::IdPlural)
+                    },
+                    "msgstr" => {
+                        entry.strs = vec![value];
+                        Some(PoField::Str(0))
+                    },
+                    _ => {
+                        let index = keyword
+                            .strip_prefix("msgstr[")
+                            .and_then(|index| index.strip_suffix(']'))
+                            .and_then(|index| index.parse::<usize>().ok());
+                        match index {
+                            Some(index) => {
+                                if entry.strs.len() <= index {
+                                    entry.strs.resize(index + 1, String::new());
+                                }
+                                entry.strs[index] = value;
+                                Some(PoField::Str(index))
+                            },
+                            None => {
+                                godot_warn!("Unknown keyword in gettext file: {}", keyword);
+                                None
+                            },
+                        }
+                    },
+                };
+            }
+        }
+        if !entry.strs.is_empty() {
+            entries.push(entry);
+        }
+        entries
+    }
+
+    /// Parses comma-separated values like [method FileAccess.get_csv_line]:
+    /// quoted cells may contain commas and line breaks, and quotes are escaped by doubling them.
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut rows = vec![];
+        let mut row = vec![];
+        let mut cell = String::new();
+        let mut in_quotes = false;
+        let mut chars = text.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '"' if in_quotes => {
+                    if chars.peek() == Some(&'"') {
+                        cell.push('"');
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                    }
+                },
+                '"' if cell.is_empty() => in_quotes = true,
+                ',' if !in_quotes => row.push(std::mem::take(&mut cell)),
+                '\r' if !in_quotes && chars.peek() == Some(&'\n') => {},
+                '\n' if !in_quotes => {
+                    row.push(std::mem::take(&mut cell));
+                    rows.push(std::mem::take(&mut row));
+                },
+                _ => cell.push(char),
+            }
+        }
+        if !cell.is_empty() || !row.is_empty() {
+            row.push(cell);
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn unquote(value: &str) -> String {
+        let value = value.strip_prefix('"').unwrap_or(value);
+        let value = value.strip_suffix('"').unwrap_or(value);
+
+        let mut result = String::new();
+        let mut chars = value.chars();
+        while let Some(char) = chars.next() {
+            if char != '\\' {
+                result.push(char);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        }
+        result
+    }
+}
+
+/// Collects converted messages in order, merging values and attributes of the same identifier.
+struct MessageBuilder {
+    invalid_message_handling: i32,
+    messages: Vec<ast::Message<String>>,
+    indices: HashMap<String, usize>,
+    skipped: usize,
+}
+
+impl MessageBuilder {
+    fn new(invalid_message_handling: i32) -> Self {
+        Self {
+            invalid_message_handling,
+            messages: vec![],
+            indices: HashMap::new(),
+            skipped: 0,
+        }
+    }
+
+    fn add_value(&mut self, id: &String, value: ast::Pattern<String>, comments: Vec<String>) {
+        let Some(message) = self.get_message(id, comments) else {
+            return;
+        };
+        if message.value.is_some() {
+            godot_warn!("Message {} is defined multiple times, ignoring duplicate.", id);
+            return;
+        }
+        message.value = Some(value);
+    }
+
+    fn add_attribute(&mut self, id: &String, attribute: &String, value: ast::Pattern<String>, comments: Vec<String>) {
+        let Some(attribute) = self.make_identifier(attribute) else {
+            return;
+        };
+        let Some(message) = self.get_message(id, comments) else {
+            return;
+        };
+        if message.attributes.iter().any(|existing| existing.id.name == attribute) {
+            godot_warn!("Attribute {}.{} is defined multiple times, ignoring duplicate.", id, attribute);
+            return;
+        }
+        message.attributes.push(ast::Attribute {
+            id: ast::Identifier { name: attribute },
+            value,
+        });
+    }
+
+    fn get_message(&mut self, id: &String, comments: Vec<String>) -> Option<&mut ast::Message<String>> {
+        let id = self.make_identifier(id)?;
+        let index = *self.indices.entry(id.clone()).or_insert_with(|| {
+            self.messages.push(ast::Message {
+                id: ast::Identifier { name: id },
+                value: None,
+                attributes: Default::default(),
+                comment: None,
+            });
+            self.messages.len() - 1
+        });
+
+        let message = &mut self.messages[index];
+        if !comments.is_empty() {
+            message.comment.get_or_insert_with(|| ast::Comment { content: vec![] }).content.extend(comments);
+        }
+        Some(message)
+    }
+
+    fn make_identifier(&mut self, name: &String) -> Option<String> {
+        let safe_name = FluentGenerator::make_safe_identifier(name);
+        if *name != safe_name && self.invalid_message_handling == INVALID_MESSAGE_HANDLING_SKIP {
+            self.skipped += 1;
+            return None;
+        }
+        Some(safe_name)
+    }
+
+    fn write(self, path: &GString) -> GdErr {
+        if self.skipped > 0 {
+            godot_warn!("{} skipped {} messages with invalid identifiers.", path, self.skipped);
+        }
+
+        let ftl = ast::Resource {
+            body: self.messages.into_iter().map(ast::Entry::Message).collect(),
+        };
+        let ftl = serialize(&ftl);
+        match create_file_for_write(path) {
+            Ok(mut fa) => {
+                fa.store_string(&ftl);
+                GdErr::OK
+            },
+            Err(err) => {
+                godot_error!("Unable to open file {} for writing: {}", path, error_string(err.ord() as i64));
+                err
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multiline_po_strings() {
+        let entries = FluentConverter::parse_po(concat!(
+            "msgid \"\"\n",
+            "\"First line \"\n",
+            "\"continued\"\n",
+            "msgstr \"\"\n",
+            "\"Erste Zeile \"\n",
+            "\"fortgesetzt\"\n",
+        ));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "First line continued");
+        assert_eq!(entries[0].strs, vec!["Erste Zeile fortgesetzt".to_owned()]);
+    }
+
+    #[test]
+    fn unescapes_po_strings() {
+        let entries = FluentConverter::parse_po(concat!(
+            "msgid \"Say \\\"hi\\\"\\nthen\\tleave \\\\o/\"\n",
+            "msgstr \"Sag \\\"hallo\\\"\\ndann\\tgeh \\\\o/\"\n",
+        ));
+        assert_eq!(entries[0].id, "Say \"hi\"\nthen\tleave \\o/");
+        assert_eq!(entries[0].strs[0], "Sag \"hallo\"\ndann\tgeh \\o/");
+    }
+
+    #[test]
+    fn parses_po_context_comments_and_flags() {
+        let entries = FluentConverter::parse_po(concat!(
+            "#. Shown on the main menu\n",
+            "#: menu.tscn:12\n",
+            "#, fuzzy, c-format\n",
+            "msgctxt \"menu\"\n",
+            "msgid \"Open\"\n",
+            "msgstr \"Öffnen\"\n",
+            "\n",
+            "msgctxt \"\"\n",
+            "\"door\"\n",
+            "msgid \"Open\"\n",
+            "msgstr \"Offen\"\n",
+        ));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].comments, vec!["Shown on the main menu".to_owned()]);
+        assert!(entries[0].fuzzy);
+        assert_eq!(entries[0].context.as_deref(), Some("menu"));
+        assert!(!entries[1].fuzzy);
+        assert_eq!(entries[1].context.as_deref(), Some("door"));
+        assert_eq!(entries[1].strs[0], "Offen");
+    }
+
+    #[test]
+    fn parses_po_plural_forms() {
+        let entries = FluentConverter::parse_po(concat!(
+            "msgid \"%d apple\"\n",
+            "msgid_plural \"%d apples\"\n",
+            "msgstr[0] \"%d jabłko\"\n",
+            "msgstr[1] \"%d jabłka\"\n",
+            "msgstr[2] \"\"\n",
+            "\"%d jabłek\"\n",
+        ));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id_plural.as_deref(), Some("%d apples"));
+        assert_eq!(entries[0].strs, vec!["%d jabłko".to_owned(), "%d jabłka".to_owned(), "%d jabłek".to_owned()]);
+    }
+
+    #[test]
+    fn finds_plural_categories() {
+        assert_eq!(FluentConverter::get_plural_categories(Some("de"), false), vec!["one", "other"]);
+        assert_eq!(FluentConverter::get_plural_categories(Some("pl"), false), vec!["one", "few", "many"]);
+        assert_eq!(FluentConverter::get_plural_categories(Some("ja"), false), vec!["other"]);
+        assert_eq!(FluentConverter::get_plural_categories(Some("pl"), true), vec!["one", "other"]);
+    }
+
+    #[test]
+    fn parses_quoted_csv_cells() {
+        let rows = FluentConverter::parse_csv(concat!(
+            "keys,en,de\r\n",
+            "GREETING,\"Hello,\nworld\",\"Hallo \"\"Welt\"\"\"\n",
+            "EMPTY,,\n",
+        ));
+        assert_eq!(rows, vec![
+            vec!["keys", "en", "de"],
+            vec!["GREETING", "Hello,\nworld", "Hallo \"Welt\""],
+            vec!["EMPTY", "", ""],
+        ]);
+    }
+}
@@ -1,12 +1,17 @@
-use godot::{classes::{EditorPlugin, IEditorPlugin}, prelude::*};
+use godot::{classes::{editor_plugin::DockSlot, EditorInterface, EditorPlugin, IEditorPlugin, ProjectSettings}, prelude::*};
 
-use super::FluentExportPlugin;
+use super::project_settings::PROJECT_SETTING_GENERATOR_REGENERATE_ON_SAVE;
+use super::{FluentExportPlugin, FluentGenerator, FluentGeneratorDock, ResourceFormatLoaderFluent};
+
+/// Name of the Project > Tools menu entry which runs the [FluentGenerator](super::FluentGenerator).
+const GENERATE_MENU_ITEM: &str = "Generate Fluent Translations";
 
 /// Editor plugin to register tools for Fluent Translations. For internal use only.
 #[derive(GodotClass)]
 #[class(tool, init, base=EditorPlugin)]
 pub struct FluentEditorPlugin {
     export_plugin: Option<Gd<FluentExportPlugin>>,
+    generator_dock: Option<Gd<FluentGeneratorDock>>,
     base: Base<EditorPlugin>,
 }
 
@@ -16,6 +21,18 @@ impl IEditorPlugin for FluentEditorPlugin {
         let export_plugin = FluentExportPlugin::new_gd();
         self.export_plugin = Some(export_plugin.clone());
         self.base_mut().add_export_plugin(&export_plugin);
+
+        let generator_dock = FluentGeneratorDock::new_alloc();
+        self.generator_dock = Some(generator_dock.clone());
+        self.base_mut().add_control_to_dock(DockSlot::RIGHT_UL, &generator_dock);
+        self.base_mut().add_tool_menu_item(GENERATE_MENU_ITEM, &generator_dock.callable("generate"));
+
+        let on_resource_saved = self.base().callable("on_resource_saved");
+        self.base_mut().connect("resource_saved", &on_resource_saved);
+
+        if let Some(mut file_system) = EditorInterface::singleton().get_resource_filesystem() {
+            file_system.connect("filesystem_changed", &self.base().callable("on_filesystem_changed"));
+        }
     }
 
     fn exit_tree(&mut self) {
@@ -23,5 +40,45 @@ impl IEditorPlugin for FluentEditorPlugin {
             self.base_mut().remove_export_plugin(&export_plugin);
             self.export_plugin = None;
         }
+        let on_resource_saved = self.base().callable("on_resource_saved");
+        self.base_mut().disconnect("resource_saved", &on_resource_saved);
+        if let Some(mut file_system) = EditorInterface::singleton().get_resource_filesystem() {
+            file_system.disconnect("filesystem_changed", &self.base().callable("on_filesystem_changed"));
+        }
+        if let Some(generator_dock) = self.generator_dock.take() {
+            self.base_mut().remove_tool_menu_item(GENERATE_MENU_ITEM);
+            self.base_mut().remove_control_from_docks(&generator_dock);
+            generator_dock.free();
+        }
+    }
+}
+
+#[godot_api]
+impl FluentEditorPlugin {
+    /// Shared terms files may have been added, renamed or deleted.
+    #[func]
+    fn on_filesystem_changed(&mut self) {
+        ResourceFormatLoaderFluent::clear_terms_files_cache();
+    }
+
+    /// Updates the FTL files of the saved resource for the Project Settings and all profiles, if enabled via the `generator/regenerate_on_save` Project Setting.
+    #[func]
+    fn on_resource_saved(&mut self, resource: Gd<Resource>) {
+        let enabled = bool::from_variant(&ProjectSettings::singleton().get_setting(PROJECT_SETTING_GENERATOR_REGENERATE_ON_SAVE));
+        let path = resource.get_path();
+        if !enabled || path.is_empty() {
+            return;
+        }
+
+        let profiles = FluentGenerator::get_profiles();
+        let profiles = std::iter::once(None).chain(profiles.as_slice().iter().map(Some));
+        for generator in profiles.filter_map(FluentGenerator::create_for_profile) {
+            let report = generator.bind().generate_for_sources(PackedStringArray::from(&[path.clone()]));
+            if let Some(mut file_system) = EditorInterface::singleton().get_resource_filesystem() {
+                for ftl in report.keys_array().iter_shared() {
+                    file_system.update_file(&ftl.stringify());
+                }
+            }
+        }
     }
 }
@@ -1,10 +1,163 @@
+use std::collections::{hash_map::Entry, BTreeMap};
+
+use fluent_syntax::ast;
 use godot::prelude::*;
 
-use super::MessageGeneration;
+use super::extractor_script::variant_to_strings;
+use super::{pattern, MessageGeneration};
 
 // There's currently no way to create classes that inherit from others (upstream https://github.com/godot-rust/gdext/issues/426)
-// So for now we create a trait but don't register or use it for the public API. A plugin system can always be retrofitted here later.
+// So for now we create a trait but don't register it for the public API. Scripts can still provide extractors via FluentGenerator::add_extractor.
 pub trait FluentTranslationParser {
     fn get_recognized_extensions(&self) -> Vec<GString>;
     fn extract_messages(&self, path: &GString) -> MessageGeneration;
+
+    /// Whether the extracted messages only depend on the content of the file and its dependencies,
+    /// so that they can be reused as long as those don't change.
+    fn is_cacheable(&self) -> bool {
+        true
+    }
+}
+
+/// A message found by a [FluentTranslationParser], which is written to FTL files if it does not exist yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractedMessage {
+    pub value: ast::Pattern<String>,
+    /// Where the message was found, e.g. `res://menu.tscn:VBox/Start` or `res://player.gd:42`.
+    pub references: Vec<String>,
+    /// Attributes by name, e.g. `tooltip` for a Control's `tooltip_text`.
+    pub attributes: BTreeMap<String, ast::Pattern<String>>,
+    /// Comments for translators, e.g. from node metadata, which are written as `# Note: ...` comments.
+    pub comments: Vec<String>,
+    /// Set for messages whose id is their source text, which allows the generator to derive another id (see `generator/id_strategy`).
+    /// Such messages are inserted by [Self::context_key] instead of their text.
+    pub id_context: Option<IdContext>,
+}
+
+/// Information to derive the id of an extracted message from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdContext {
+    /// The source text.
+    pub text: String,
+    /// Location of the text, e.g. `main_menu/VBox/Start/text`.
+    pub path: String,
+    /// Id explicitly assigned to the text, e.g. via node metadata.
+    pub explicit_id: Option<String>,
+}
+
+/// Adds a message, merging its references into those of an already extracted message with the same id.
+pub fn insert_message(messages: &mut MessageGeneration, id: String, message: ExtractedMessage) {
+    match messages.entry(id) {
+        Entry::Occupied(mut entry) => {
+            let existing = entry.get_mut();
+            existing.references.extend(message.references);
+            for (name, value) in message.attributes {
+                existing.attributes.entry(name).or_insert(value);
+            }
+            for comment in message.comments {
+                if !existing.comments.contains(&comment) {
+                    existing.comments.push(comment);
+                }
+            }
+        },
+        Entry::Vacant(entry) => {
+            entry.insert(message);
+        },
+    }
+}
+
+/// Same as [insert_message], for all messages of `other`.
+pub fn merge_messages(messages: &mut MessageGeneration, other: MessageGeneration) {
+    for (id, message) in other {
+        insert_message(messages, id, message);
+    }
+}
+
+impl ExtractedMessage {
+    pub fn from_text(text: &str) -> Self {
+        Self {
+            value: pattern::text_to_pattern(text),
+            references: vec![],
+            attributes: Default::default(),
+            comments: vec![],
+            id_context: None,
+        }
+    }
+
+    pub fn with_reference(mut self, reference: String) -> Self {
+        self.references.push(reference);
+        self
+    }
+
+    /// Marks the message's id as its source text, found at `path`.
+    pub fn with_id_context(mut self, text: &str, path: String, explicit_id: Option<String>) -> Self {
+        self.id_context = Some(IdContext {
+            text: text.to_owned(),
+            path,
+            explicit_id,
+        });
+        self
+    }
+
+    /// Adds a comment for translators, one per line of the text. Empty lines are skipped.
+    pub fn with_comment(mut self, text: &str) -> Self {
+        self.comments.extend(text.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_owned));
+        self
+    }
+
+    /// Returns the key to insert a message with an id context by, which differs for each text, location and explicit id.
+    /// This prevents merging texts that result in different ids, e.g. the same text with different explicit ids.
+    pub fn context_key(&self) -> Option<String> {
+        let context = self.id_context.as_ref()?;
+        Some(format!("{}\n{}\n{}", context.path, context.text, context.explicit_id.as_deref().unwrap_or_default()))
+    }
+
+    pub fn with_attribute(mut self, name: String, text: &str) -> Self {
+        self.attributes.insert(name, Self::from_text(text).value);
+        self
+    }
+
+    /// Creates a message skeleton, which receives a placeable for each of the given variables.
+    pub fn with_variables(text: &str, variables: &[String]) -> Self {
+        let mut elements = vec![ast::PatternElement::TextElement { value: text.to_owned() }];
+        for variable in variables {
+            elements.push(ast::PatternElement::TextElement { value: " ".to_owned() });
+            elements.push(ast::PatternElement::Placeable {
+                expression: ast::Expression::Inline(ast::InlineExpression::VariableReference {
+                    id: ast::Identifier { name: variable.clone() },
+                }),
+            });
+        }
+        let mut message = Self::from_text(text);
+        message.value = pattern::elements_to_pattern(elements);
+        message
+    }
+
+    /// Converts the value returned by a script extractor for the message `id`:
+    /// either the message text, or a Dictionary with the optional keys `text`, `variables`, `references` and `comment`.
+    pub fn try_from_variant(id: &str, value: &Variant) -> Option<Self> {
+        match value.get_type() {
+            VariantType::STRING | VariantType::STRING_NAME => Some(Self::from_text(&value.stringify().to_string())),
+            VariantType::DICTIONARY => {
+                let value = Dictionary::from_variant(value);
+                let text = match value.get("text") {
+                    Some(text) => text.try_to::<GString>().ok()?.to_string(),
+                    None => id.to_owned(),
+                };
+                let variables = match value.get("variables") {
+                    Some(variables) => variant_to_strings(&variables)?,
+                    None => vec![],
+                };
+                let mut message = Self::with_variables(&text, &variables);
+                if let Some(references) = value.get("references") {
+                    message.references = variant_to_strings(&references)?;
+                }
+                if let Some(comment) = value.get("comment") {
+                    message = message.with_comment(&comment.try_to::<GString>().ok()?.to_string());
+                }
+                Some(message)
+            },
+            _ => None,
+        }
+    }
 }
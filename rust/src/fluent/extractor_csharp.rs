@@ -0,0 +1,555 @@
+use godot::classes::FileAccess;
+use godot::global::Error as GdErr;
+use godot::prelude::*;
+
+use super::{insert_message, ExtractedMessage, FluentGenerator, FluentTranslationParser, MessageGeneration};
+
+/// Methods whose first argument is a message id.
+const TRANSLATION_METHODS: [&str; 4] = ["Tr", "Atr", "TrN", "AtrN"];
+
+/// Extracts messages from C# files, by looking for invocations of translation methods with a literal message id.
+///
+/// Recognized are `Tr`, `Atr`, `TrN`, `AtrN` and `TranslationFluent.Args`.
+/// If the args are passed as a collection or anonymous object initializer, a placeable is generated for each of its literal keys.
+pub struct FluentCSharpTranslationParser;
+
+impl FluentTranslationParser for FluentCSharpTranslationParser {
+    fn get_recognized_extensions(&self) -> Vec<GString> {
+        vec!["cs".into()]
+    }
+
+    fn extract_messages(&self, path: &GString) -> MessageGeneration {
+        let source = FileAccess::get_file_as_string(path);
+        if FileAccess::get_open_error() != GdErr::OK {
+            godot_error!("Failed loading {path}.");
+            return Default::default();
+        }
+        self.extract_from_source(&path.to_string(), &source.to_string())
+    }
+}
+
+impl FluentCSharpTranslationParser {
+    pub fn init() -> Self {
+        Self
+    }
+
+    /// Extracts the messages of a script's source code, referencing them by `path` and line.
+    pub fn extract_from_source(&self, path: &str, source: &str) -> MessageGeneration {
+        let (messages, unterminated_lines) = Self::scan_source(path, source);
+        for line in unterminated_lines {
+            godot_warn!("Unterminated string literal at {path}:{line}, skipping it.");
+        }
+        messages
+    }
+
+    /// Returns the messages of a script's source code, and the lines of unterminated string literals.
+    fn scan_source(path: &str, source: &str) -> (MessageGeneration, Vec<usize>) {
+        let chars = source.chars().collect::<Vec<_>>();
+        let line_starts = line_starts(&chars);
+        let mut messages = MessageGeneration::new();
+        let mut unterminated_lines = vec![];
+        let mut index = 0;
+        while index < chars.len() {
+            let char = chars[index];
+            if let Some(end) = skip_comment(&chars, index) {
+                index = end;
+            } else if let Some(end) = skip_literal(&chars, index) {
+                if end.is_err() {
+                    unterminated_lines.push(line_starts.partition_point(|line_start| *line_start <= index));
+                }
+                let (Ok(end) | Err(end)) = end;
+                index = end;
+            } else if char.is_alphabetic() || char == '_' {
+                let start = index;
+                index = skip_identifier(&chars, index);
+
+                let identifier = chars[start..index].iter().collect::<String>();
+                let is_translation_call = TRANSLATION_METHODS.contains(&identifier.as_str())
+                    || (identifier == "Args" && is_preceded_by(&chars, start, "TranslationFluent."));
+                if !is_translation_call || is_declaration(&chars, start) {
+                    continue;
+                }
+
+                if let Some((id, variables)) = Self::parse_invocation(&chars, index) {
+                    if !id.is_empty() {
+                        let line = line_starts.partition_point(|line_start| *line_start <= start);
+                        let message = ExtractedMessage::with_variables(&id, &variables).with_reference(format!("{path}:{line}"));
+                        insert_message(&mut messages, id, message);
+                    }
+                }
+            } else {
+                index += 1;
+            }
+        }
+        (messages, unterminated_lines)
+    }
+
+    /// Parses the arguments of an invocation, returning the literal message id and the literal keys of the args initializer.
+    fn parse_invocation(chars: &[char], index: usize) -> Option<(String, Vec<String>)> {
+        let mut index = skip_trivia(chars, index);
+        if chars.get(index) != Some(&'(') {
+            return None;
+        }
+        index = skip_trivia(chars, index + 1);
+
+        // Named argument, e.g. `Tr(message: "id")`.
+        let name_end = skip_identifier(chars, index);
+        if name_end > index && chars.get(skip_trivia(chars, name_end)) == Some(&':') {
+            index = skip_trivia(chars, skip_trivia(chars, name_end) + 1);
+        }
+
+        let (id, end) = parse_string(chars, index)?;
+
+        let mut variables = vec![];
+        index = skip_trivia(chars, end);
+        if chars.get(index) == Some(&',') {
+            index = skip_trivia(chars, index + 1);
+            if let Some(open_index) = Self::find_initializer(chars, index) {
+                variables = Self::parse_initializer_keys(chars, open_index);
+            }
+        }
+        Some((id, variables))
+    }
+
+    /// Skips an object creation expression like `new Dictionary()`, `new()` or `new`, returning the index of its initializer brace.
+    fn find_initializer(chars: &[char], index: usize) -> Option<usize> {
+        let keyword_end = skip_identifier(chars, index);
+        if chars[index..keyword_end].iter().collect::<String>() != "new" {
+            return None;
+        }
+
+        let mut index = skip_trivia(chars, keyword_end);
+        let mut depth = 0;
+        while index < chars.len() {
+            match chars[index] {
+                '{' if depth == 0 => return Some(index),
+                '(' | '<' | '[' => depth += 1,
+                ')' | '>' | ']' => depth -= 1,
+                ',' | ';' if depth == 0 => return None,
+                _ => {},
+            }
+            index += 1;
+        }
+        None
+    }
+
+    /// Returns the literal keys of an initializer, in `{ { "key", value } }`, `{ ["key"] = value }` and `{ key = value }` syntax.
+    fn parse_initializer_keys(chars: &[char], open_index: usize) -> Vec<String> {
+        let mut keys = vec![];
+        let mut depth = 0;
+        let mut entry_start = open_index + 1;
+        let mut index = open_index;
+        while index < chars.len() {
+            if let Some(end) = skip_comment(chars, index) {
+                index = end;
+                continue;
+            }
+            if let Some(Ok(end) | Err(end)) = skip_literal(chars, index) {
+                index = end;
+                continue;
+            }
+
+            let char = chars[index];
+            match char {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {},
+            }
+            if (char == ',' && depth == 1) || depth == 0 {
+                keys.extend(Self::parse_initializer_key(&chars[entry_start..index]));
+                entry_start = index + 1;
+            }
+            if depth == 0 {
+                break;
+            }
+            index += 1;
+        }
+        keys
+    }
+
+    fn parse_initializer_key(entry: &[char]) -> Option<String> {
+        let index = skip_trivia(entry, 0);
+        let key = match entry.get(index)? {
+            '{' => {
+                // Collection initializer: `{ "key", value }`.
+                let (key, end) = parse_string(entry, skip_trivia(entry, index + 1))?;
+                if entry.get(skip_trivia(entry, end)) != Some(&',') {
+                    return None;
+                }
+                key
+            },
+            '[' => {
+                // Index initializer: `["key"] = value`.
+                let (key, end) = parse_string(entry, skip_trivia(entry, index + 1))?;
+                let end = skip_trivia(entry, end);
+                if entry.get(end) != Some(&']') || entry.get(skip_trivia(entry, end + 1)) != Some(&'=') {
+                    return None;
+                }
+                key
+            },
+            char if char.is_alphabetic() || *char == '_' => {
+                // Anonymous object or member initializer: `key = value`.
+                let end = skip_identifier(entry, index);
+                if entry.get(skip_trivia(entry, end)) != Some(&'=') {
+                    return None;
+                }
+                entry[index..end].iter().collect::<String>()
+            },
+            _ => return None,
+        };
+
+        // Keys must be valid variable names.
+        if key.is_empty() || FluentGenerator::make_safe_identifier(&key) != key {
+            return None;
+        }
+        Some(key)
+    }
+}
+
+/// Returns the index of the first character of each line.
+fn line_starts(chars: &[char]) -> Vec<usize> {
+    let newlines = chars.iter().enumerate().filter(|(_, char)| **char == '\n').map(|(index, _)| index + 1);
+    std::iter::once(0).chain(newlines).collect()
+}
+
+fn is_preceded_by(chars: &[char], index: usize, prefix: &str) -> bool {
+    let prefix = prefix.chars().collect::<Vec<_>>();
+    index >= prefix.len() && chars[index - prefix.len()..index] == prefix[..]
+}
+
+/// Whether the identifier is preceded by a type name (e.g. `string Tr(...)`), i.e. declared rather than invoked.
+fn is_declaration(chars: &[char], index: usize) -> bool {
+    let mut start = index;
+    while start > 0 && chars[start - 1].is_whitespace() {
+        start -= 1;
+    }
+    if start == 0 || start == index || !(chars[start - 1].is_alphanumeric() || matches!(chars[start - 1], '_' | ']')) {
+        return false;
+    }
+    let mut token_start = start;
+    while token_start > 0 && (chars[token_start - 1].is_alphanumeric() || chars[token_start - 1] == '_') {
+        token_start -= 1;
+    }
+    let token = chars[token_start..start].iter().collect::<String>();
+    !["return", "await", "in", "else"].contains(&token.as_str())
+}
+
+fn skip_identifier(chars: &[char], mut index: usize) -> usize {
+    if chars.get(index) == Some(&'@') {
+        index += 1;
+    }
+    while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+        index += 1;
+    }
+    index
+}
+
+fn skip_trivia(chars: &[char], mut index: usize) -> usize {
+    loop {
+        while index < chars.len() && chars[index].is_whitespace() {
+            index += 1;
+        }
+        match skip_comment(chars, index) {
+            Some(end) => index = end,
+            None => return index,
+        }
+    }
+}
+
+/// Returns the index after the comment, if one starts at the given index.
+fn skip_comment(chars: &[char], index: usize) -> Option<usize> {
+    if chars.get(index) != Some(&'/') {
+        return None;
+    }
+    match chars.get(index + 1) {
+        Some('/') => {
+            let mut index = index + 2;
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+            Some(index)
+        },
+        Some('*') => {
+            let mut index = index + 2;
+            while index < chars.len() && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/')) {
+                index += 1;
+            }
+            Some((index + 2).min(chars.len()))
+        },
+        _ => None,
+    }
+}
+
+/// Returns the index after the string or character literal, if one starts at the given index.
+/// If the literal is unterminated, returns an error with the index to continue at instead.
+fn skip_literal(chars: &[char], index: usize) -> Option<Result<usize, usize>> {
+    match chars.get(index)? {
+        '\'' => {
+            let mut index = index + 1;
+            while index < chars.len() && chars[index] != '\'' && chars[index] != '\n' {
+                if chars[index] == '\\' {
+                    index += 1;
+                }
+                index += 1;
+            }
+            match chars.get(index) {
+                Some('\'') => Some(Ok(index + 1)),
+                _ => Some(Err(index.min(chars.len()))),
+            }
+        },
+        '"' | '@' | '$' => {
+            let (prefix_length, is_verbatim, is_interpolated) = parse_string_prefix(chars, index)?;
+            if is_interpolated {
+                return Some(skip_interpolated_string(chars, index + prefix_length, is_verbatim));
+            }
+            match parse_string(chars, index) {
+                Some((_, end)) => Some(Ok(end)),
+                // Only verbatim and raw strings may span multiple lines.
+                None if !is_verbatim && count_quotes(chars, index) < 3 => Some(Err(find_line_end(chars, index))),
+                None => Some(Err(chars.len())),
+            }
+        },
+        _ => None,
+    }
+}
+
+fn find_line_end(chars: &[char], index: usize) -> usize {
+    chars[index..].iter().position(|char| *char == '\n').map_or(chars.len(), |length| index + length)
+}
+
+/// Returns the length of a string prefix up to (excluding) the quote, and whether the string is verbatim or interpolated.
+fn parse_string_prefix(chars: &[char], index: usize) -> Option<(usize, bool, bool)> {
+    let mut length = 0;
+    let mut is_verbatim = false;
+    let mut is_interpolated = false;
+    while let Some(char) = chars.get(index + length) {
+        match char {
+            '@' if !is_verbatim => is_verbatim = true,
+            '$' => is_interpolated = true,
+            '"' => return Some((length, is_verbatim, is_interpolated)),
+            _ => return None,
+        }
+        length += 1;
+    }
+    None
+}
+
+/// Skips an interpolated string, including string literals nested in its interpolation holes.
+/// If the string is unterminated, returns an error with the index to continue at instead.
+fn skip_interpolated_string(chars: &[char], index: usize, is_verbatim: bool) -> Result<usize, usize> {
+    let quote_count = count_quotes(chars, index);
+    let is_raw = quote_count >= 3;
+    let mut index = index + if is_raw { quote_count } else { 1 };
+    let mut depth = 0;
+    while index < chars.len() {
+        let char = chars[index];
+        if depth > 0 {
+            if let Some(Ok(end) | Err(end)) = skip_literal(chars, index) {
+                index = end;
+                continue;
+            }
+            match char {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {},
+            }
+        } else if char == '{' {
+            if chars.get(index + 1) == Some(&'{') && !is_raw {
+                index += 1;
+            } else {
+                depth += 1;
+            }
+        } else if char == '\\' && !is_verbatim && !is_raw {
+            index += 1;
+        } else if char == '\n' && !is_verbatim && !is_raw {
+            return Err(index);
+        } else if char == '"' {
+            if is_raw {
+                if count_quotes(chars, index) >= quote_count {
+                    return Ok(index + quote_count);
+                }
+            } else if is_verbatim && chars.get(index + 1) == Some(&'"') {
+                index += 1;
+            } else {
+                return Ok(index + 1);
+            }
+        }
+        index += 1;
+    }
+    Err(chars.len())
+}
+
+fn count_quotes(chars: &[char], index: usize) -> usize {
+    chars[index.min(chars.len())..].iter().take_while(|char| **char == '"').count()
+}
+
+/// Parses a regular (`"..."`), verbatim (`@"..."`) or raw (`"""..."""`) string literal starting at the prefix or quote,
+/// returning its value and the index after the closing quote.
+fn parse_string(chars: &[char], index: usize) -> Option<(String, usize)> {
+    let is_verbatim = chars.get(index) == Some(&'@');
+    let index = index + usize::from(is_verbatim);
+    if chars.get(index) != Some(&'"') {
+        return None;
+    }
+
+    let quote_count = count_quotes(chars, index);
+    if quote_count >= 3 {
+        // Raw string literal.
+        let mut end = index + quote_count;
+        while end < chars.len() {
+            if chars[end] == '"' && count_quotes(chars, end) >= quote_count {
+                let value = chars[index + quote_count..end].iter().collect::<String>();
+                return Some((trim_raw_string(&value), end + quote_count));
+            }
+            end += 1;
+        }
+        return None;
+    }
+
+    let mut value = String::new();
+    let mut index = index + 1;
+    while index < chars.len() {
+        let char = chars[index];
+        if char == '"' {
+            if is_verbatim && chars.get(index + 1) == Some(&'"') {
+                value.push('"');
+                index += 2;
+                continue;
+            }
+            return Some((value, index + 1));
+        }
+        if char == '\n' && !is_verbatim {
+            // Unterminated string.
+            return None;
+        }
+
+        if char == '\\' && !is_verbatim {
+            index += 1;
+            match chars.get(index) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('0') => value.push('\0'),
+                Some(escape @ ('u' | 'x' | 'U')) => {
+                    let max_length = if *escape == 'U' { 8 } else { 4 };
+                    let length = chars[index + 1..].iter().take(max_length).take_while(|char| char.is_ascii_hexdigit()).count();
+                    let hex = chars[index + 1..index + 1 + length].iter().collect::<String>();
+                    value.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    index += length;
+                },
+                Some(other) => value.push(*other),
+                None => return None,
+            }
+        } else {
+            value.push(char);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Removes the leading and trailing line of a multi-line raw string literal, as well as the indentation of the closing quotes.
+fn trim_raw_string(value: &str) -> String {
+    if !value.contains('\n') {
+        return value.to_owned();
+    }
+    let mut lines = value.lines().collect::<Vec<_>>();
+    let indentation = lines.last().filter(|line| line.trim().is_empty()).map_or(0, |line| line.len());
+    if lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines
+        .into_iter()
+        .map(|line| line.get(indentation..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_syntax::ast;
+
+    use super::*;
+
+    fn scan(source: &str) -> (MessageGeneration, Vec<usize>) {
+        FluentCSharpTranslationParser::scan_source("res://test.cs", source)
+    }
+
+    fn ids(messages: &MessageGeneration) -> Vec<&str> {
+        let mut ids = messages.keys().map(String::as_str).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn variables(message: &ExtractedMessage) -> Vec<&str> {
+        message.value.elements.iter().filter_map(|element| match element {
+            ast::PatternElement::Placeable { expression: ast::Expression::Inline(ast::InlineExpression::VariableReference { id }) } => Some(id.name.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn extracts_invocations_and_initializer_keys() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "Tr(\"plain\");\n",
+            "TrN(\"apples\", \"apples\", count);\n",
+            "Atr(message: \"named\");\n",
+            "Tr(TranslationFluent.Args(\"args\", new Dictionary { { \"name\", name }, [\"count\"] = 1 }));\n",
+            "TranslationFluent.Args(\"anonymous\", new { player = name, Nested = new { inner = 1 } });\n",
+        ));
+        assert!(unterminated_lines.is_empty());
+        assert_eq!(ids(&messages), vec!["anonymous", "apples", "args", "named", "plain"]);
+        assert_eq!(variables(&messages["args"]), vec!["name", "count"]);
+        assert_eq!(variables(&messages["anonymous"]), vec!["player", "Nested"]);
+        assert_eq!(messages["apples"].references, vec!["res://test.cs:2".to_owned()]);
+    }
+
+    #[test]
+    fn skips_interpolated_verbatim_and_raw_strings() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "var a = $@\"C:\\{folder}\\Tr(\"\"verbatim\"\") {{ {Format(\"}\")} \";\n",
+            "var b = $\"{(x ? \"Tr(\\\"nested\\\")\" : y)} Tr(\\\"escaped\\\")\";\n",
+            "var c = \"\"\"\n",
+            "    Tr(\"raw\")\n",
+            "    \"\"\";\n",
+            "Tr(\"\"\"raw id\"\"\");\n",
+            "Tr(@\"C:\\path\");\n",
+            "Tr(\"after\");\n",
+        ));
+        assert!(unterminated_lines.is_empty());
+        assert_eq!(ids(&messages), vec!["C:\\path", "after", "raw id"]);
+    }
+
+    #[test]
+    fn skips_declarations() {
+        let (messages, _) = scan(concat!(
+            "public static string Tr(\"declared\") => id;\n",
+            "[Export] Tr(\"attributed\");\n",
+            "return Tr(\"returned\");\n",
+            "var x = y ? a : await Tr(\"awaited\");\n",
+            "foreach (var line in Tr(\"enumerated\")) {}\n",
+            // Ends with a keyword, but is a type name.
+            "private Domain Tr(\"domain\");\n",
+        ));
+        assert_eq!(ids(&messages), vec!["awaited", "enumerated", "returned"]);
+    }
+
+    #[test]
+    fn reports_unterminated_literals() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "var a = \"open;\n",
+            "Tr(\"second-line\");\n",
+            "var b = $\"open {x};\n",
+            "Tr(\"fourth-line\");\n",
+            "var c = 'a;\n",
+            "Tr(\"sixth-line\");\n",
+            "var d = @\"never closed;\n",
+            "Tr(swallowed);\n",
+        ));
+        assert_eq!(unterminated_lines, vec![1, 3, 5, 7]);
+        assert_eq!(ids(&messages), vec!["fourth-line", "second-line", "sixth-line"]);
+    }
+}
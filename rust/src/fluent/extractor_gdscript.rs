@@ -0,0 +1,370 @@
+use godot::classes::FileAccess;
+use godot::global::Error as GdErr;
+use godot::prelude::*;
+
+use super::{insert_message, ExtractedMessage, FluentGenerator, FluentTranslationParser, MessageGeneration};
+
+/// Functions whose first argument is a message id.
+const TRANSLATION_FUNCTIONS: [&str; 4] = ["tr", "atr", "tr_n", "atr_n"];
+
+/// Extracts messages from GDScript files, by looking for calls of translation functions with a literal message id.
+///
+/// Recognized are `tr`, `atr`, `tr_n`, `atr_n` and `TranslationFluent.args`.
+/// If a dictionary literal is passed as args, a placeable is generated for each of its literal keys.
+pub struct FluentGDScriptTranslationParser;
+
+impl FluentTranslationParser for FluentGDScriptTranslationParser {
+    fn get_recognized_extensions(&self) -> Vec<GString> {
+        vec!["gd".into()]
+    }
+
+    fn extract_messages(&self, path: &GString) -> MessageGeneration {
+        let source = FileAccess::get_file_as_string(path);
+        if FileAccess::get_open_error() != GdErr::OK {
+            godot_error!("Failed loading {path}.");
+            return Default::default();
+        }
+        self.extract_from_source(&path.to_string(), &source.to_string())
+    }
+}
+
+impl FluentGDScriptTranslationParser {
+    pub fn init() -> Self {
+        Self
+    }
+
+    /// Extracts the messages of a script's source code, referencing them by `path` and line.
+    pub fn extract_from_source(&self, path: &str, source: &str) -> MessageGeneration {
+        let (messages, unterminated_lines) = Self::scan_source(path, source);
+        for line in unterminated_lines {
+            godot_warn!("Unterminated string literal at {path}:{line}, skipping it.");
+        }
+        messages
+    }
+
+    /// Returns the messages of a script's source code, and the lines of unterminated string literals.
+    fn scan_source(path: &str, source: &str) -> (MessageGeneration, Vec<usize>) {
+        let chars = source.chars().collect::<Vec<_>>();
+        let line_starts = line_starts(&chars);
+        let mut messages = MessageGeneration::new();
+        let mut unterminated_lines = vec![];
+        let mut index = 0;
+        while index < chars.len() {
+            let char = chars[index];
+            if char == '#' {
+                index = skip_comment(&chars, index);
+            } else if char == '"' || char == '\'' {
+                let end = skip_string(&chars, index);
+                if end.is_err() {
+                    unterminated_lines.push(line_starts.partition_point(|line_start| *line_start <= index));
+                }
+                let (Ok(end) | Err(end)) = end;
+                index = end;
+            } else if char.is_alphabetic() || char == '_' {
+                let start = index;
+                while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                    index += 1;
+                }
+
+                let identifier = chars[start..index].iter().collect::<String>();
+                let is_translation_call = TRANSLATION_FUNCTIONS.contains(&identifier.as_str())
+                    || (identifier == "args" && is_preceded_by(&chars, start, "TranslationFluent."));
+                if !is_translation_call {
+                    continue;
+                }
+
+                if let Some((id, variables)) = Self::parse_call(&chars, index) {
+                    if !id.is_empty() {
+                        let line = line_starts.partition_point(|line_start| *line_start <= start);
+                        let message = ExtractedMessage::with_variables(&id, &variables).with_reference(format!("{path}:{line}"));
+                        insert_message(&mut messages, id, message);
+                    }
+                }
+            } else {
+                index += 1;
+            }
+        }
+        (messages, unterminated_lines)
+    }
+
+    /// Parses the arguments of a call, returning the literal message id and the literal keys of the args dictionary.
+    fn parse_call(chars: &[char], index: usize) -> Option<(String, Vec<String>)> {
+        let mut index = skip_trivia(chars, index);
+        if chars.get(index) != Some(&'(') {
+            return None;
+        }
+        index = skip_trivia(chars, index + 1);
+
+        // Allow StringName literals.
+        if chars.get(index) == Some(&'&') {
+            index += 1;
+        }
+        if !matches!(chars.get(index), Some('"') | Some('\'')) {
+            return None;
+        }
+        let (id, end) = parse_string(chars, index)?;
+
+        let mut variables = vec![];
+        index = skip_trivia(chars, end);
+        if chars.get(index) == Some(&',') {
+            index = skip_trivia(chars, index + 1);
+            if chars.get(index) == Some(&'{') {
+                variables = Self::parse_dictionary_keys(chars, index);
+            }
+        }
+        Some((id, variables))
+    }
+
+    /// Returns the literal keys of a dictionary literal, both in `{ "key": value }` and `{ key = value }` syntax.
+    fn parse_dictionary_keys(chars: &[char], open_index: usize) -> Vec<String> {
+        let mut keys = vec![];
+        let mut depth = 0;
+        let mut entry_start = open_index + 1;
+        let mut index = open_index;
+        while index < chars.len() {
+            let char = chars[index];
+            if char == '#' {
+                index = skip_comment(chars, index);
+                continue;
+            }
+            if char == '"' || char == '\'' {
+                let (Ok(end) | Err(end)) = skip_string(chars, index);
+                index = end;
+                continue;
+            }
+
+            match char {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {},
+            }
+            if (char == ',' && depth == 1) || depth == 0 {
+                keys.extend(Self::parse_dictionary_key(&chars[entry_start..index]));
+                entry_start = index + 1;
+            }
+            if depth == 0 {
+                break;
+            }
+            index += 1;
+        }
+        keys
+    }
+
+    fn parse_dictionary_key(entry: &[char]) -> Option<String> {
+        let mut index = skip_trivia(entry, 0);
+        if entry.get(index) == Some(&'&') {
+            index += 1;
+        }
+
+        let (key, end, separator) = match entry.get(index)? {
+            '"' | '\'' => {
+                let (key, end) = parse_string(entry, index)?;
+                (key, end, ':')
+            },
+            char if char.is_alphabetic() || *char == '_' => {
+                let start = index;
+                while index < entry.len() && (entry[index].is_alphanumeric() || entry[index] == '_') {
+                    index += 1;
+                }
+                (entry[start..index].iter().collect::<String>(), index, '=')
+            },
+            _ => return None,
+        };
+        if entry.get(skip_trivia(entry, end)) != Some(&separator) {
+            return None;
+        }
+
+        // Keys must be valid variable names.
+        if key.is_empty() || FluentGenerator::make_safe_identifier(&key) != key {
+            return None;
+        }
+        Some(key)
+    }
+}
+
+/// Returns the index of the first character of each line.
+fn line_starts(chars: &[char]) -> Vec<usize> {
+    let newlines = chars.iter().enumerate().filter(|(_, char)| **char == '\n').map(|(index, _)| index + 1);
+    std::iter::once(0).chain(newlines).collect()
+}
+
+fn is_preceded_by(chars: &[char], index: usize, prefix: &str) -> bool {
+    let prefix = prefix.chars().collect::<Vec<_>>();
+    index >= prefix.len() && chars[index - prefix.len()..index] == prefix[..]
+}
+
+/// Skips whitespace, line continuations and comments.
+fn skip_trivia(chars: &[char], mut index: usize) -> usize {
+    loop {
+        while index < chars.len() && (chars[index].is_whitespace() || chars[index] == '\\') {
+            index += 1;
+        }
+        if chars.get(index) != Some(&'#') {
+            return index;
+        }
+        index = skip_comment(chars, index);
+    }
+}
+
+fn skip_comment(chars: &[char], mut index: usize) -> usize {
+    while index < chars.len() && chars[index] != '\n' {
+        index += 1;
+    }
+    index
+}
+
+/// Returns the index after the string literal starting at the quote character.
+/// If the literal is unterminated, returns an error with the index to continue at instead.
+fn skip_string(chars: &[char], index: usize) -> Result<usize, usize> {
+    match parse_string(chars, index) {
+        Some((_, end)) => Ok(end),
+        // Only triple-quoted strings may span multiple lines.
+        None if chars.get(index + 1) == chars.get(index) && chars.get(index + 2) == chars.get(index) => Err(chars.len()),
+        None => Err(chars[index..].iter().position(|char| *char == '\n').map_or(chars.len(), |length| index + length)),
+    }
+}
+
+/// Parses a string literal starting at the quote character, returning its value and the index after the closing quote.
+fn parse_string(chars: &[char], index: usize) -> Option<(String, usize)> {
+    let quote = *chars.get(index)?;
+    let is_triple = chars.get(index + 1) == Some(&quote) && chars.get(index + 2) == Some(&quote);
+    let mut index = if is_triple { index + 3 } else { index + 1 };
+
+    let mut value = String::new();
+    while index < chars.len() {
+        let char = chars[index];
+        if char == quote {
+            if !is_triple {
+                return Some((value, index + 1));
+            }
+            if chars.get(index + 1) == Some(&quote) && chars.get(index + 2) == Some(&quote) {
+                return Some((value, index + 3));
+            }
+        }
+        if char == '\n' && !is_triple {
+            // Unterminated string.
+            return None;
+        }
+
+        if char == '\\' {
+            index += 1;
+            match chars.get(index) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('u') => {
+                    let hex = chars.get(index + 1..index + 5)?.iter().collect::<String>();
+                    value.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    index += 4;
+                },
+                Some('\n') => {},
+                Some(other) => value.push(*other),
+                None => return None,
+            }
+        } else {
+            value.push(char);
+        }
+        index += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_syntax::ast;
+
+    use super::*;
+
+    fn scan(source: &str) -> (MessageGeneration, Vec<usize>) {
+        FluentGDScriptTranslationParser::scan_source("res://test.gd", source)
+    }
+
+    fn ids(messages: &MessageGeneration) -> Vec<&str> {
+        let mut ids = messages.keys().map(String::as_str).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn variables(message: &ExtractedMessage) -> Vec<&str> {
+        message.value.elements.iter().filter_map(|element| match element {
+            ast::PatternElement::Placeable { expression: ast::Expression::Inline(ast::InlineExpression::VariableReference { id }) } => Some(id.name.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn extracts_translation_calls() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "tr(\"plain\")\n",
+            "atr('single')\n",
+            "tr_n(&\"apples\", \"apples\", count)\n",
+            "atr_n(\"pears\", \"pears\", count)\n",
+            "# tr(\"commented\")\n",
+            "var text = \"tr(\\\"quoted\\\")\"\n",
+            "translate(\"other\")\n",
+        ));
+        assert!(unterminated_lines.is_empty());
+        assert_eq!(ids(&messages), vec!["apples", "pears", "plain", "single"]);
+        assert_eq!(messages["apples"].references, vec!["res://test.gd:3".to_owned()]);
+    }
+
+    #[test]
+    fn extracts_triple_quoted_strings() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "tr(\"\"\"first\n",
+            "second \"quoted\" \"\"\")\n",
+            "var doc = '''tr(\"documented\")'''\n",
+            "tr('''single''')\n",
+        ));
+        assert!(unterminated_lines.is_empty());
+        assert_eq!(ids(&messages), vec!["first\nsecond \"quoted\" ", "single"]);
+    }
+
+    #[test]
+    fn skips_node_path_literals() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "$\"tr(x)\".text = tr(\"node\")\n",
+            "get_node(^\"Menu/tr\").text = tr(\"node-path\")\n",
+            "%\"Unique\".text = atr(\"unique\")\n",
+        ));
+        assert!(unterminated_lines.is_empty());
+        assert_eq!(ids(&messages), vec!["node", "node-path", "unique"]);
+    }
+
+    #[test]
+    fn extracts_top_level_dictionary_keys() {
+        let (messages, _) = scan(concat!(
+            "tr(TranslationFluent.args(\"inventory\", {\n",
+            "    \"item\": { \"name\": item_name, \"stats\": { \"weight\": 2 } },\n",
+            "    count = [1, { \"nested\": 2 }], # \"comment\": 3\n",
+            "    &\"owner\": player,\n",
+            "}))\n",
+        ));
+        assert_eq!(ids(&messages), vec!["inventory"]);
+        assert_eq!(variables(&messages["inventory"]), vec!["item", "count", "owner"]);
+    }
+
+    #[test]
+    fn follows_line_continuations() {
+        let (messages, _) = scan(concat!(
+            "var text = tr(\\\n",
+            "    \"continued\", \\\n",
+            "    { \"name\": name })\n",
+        ));
+        assert_eq!(ids(&messages), vec!["continued"]);
+        assert_eq!(variables(&messages["continued"]), vec!["name"]);
+        assert_eq!(messages["continued"].references, vec!["res://test.gd:1".to_owned()]);
+    }
+
+    #[test]
+    fn reports_unterminated_strings() {
+        let (messages, unterminated_lines) = scan(concat!(
+            "var a = \"open\n",
+            "tr(\"second-line\")\n",
+            "var b = \"\"\"never closed\n",
+            "tr(swallowed)\n",
+        ));
+        assert_eq!(unterminated_lines, vec![1, 3]);
+        assert_eq!(ids(&messages), vec!["second-line"]);
+    }
+}
@@ -1,18 +1,54 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 use godot::classes::resource_loader::CacheMode;
-use godot::classes::{ClassDb, RegEx, ResourceLoader, SceneState};
+use godot::classes::{ClassDb, ProjectSettings, RegEx, ResourceLoader, SceneState, Script};
 use godot::prelude::*;
 
-use super::{FluentTranslationParser, MessageGeneration};
+use super::project_settings::*;
+use super::{insert_message, merge_messages, ExtractedMessage, FluentGenerator, FluentGDScriptTranslationParser, FluentResourceTranslationParser, FluentTranslationParser, MessageGeneration};
+
+/// A text found in a scene, which is converted into an [ExtractedMessage] once the whole scene was parsed.
+struct ParsedString {
+    text: GString,
+    node_path: String,
+    property_name: String,
+    attributes: Vec<(String, GString)>,
+    explicit_id: Option<String>,
+    /// Comment for translators, from the node's metadata or `editor_description`.
+    comment: Option<String>,
+}
+
+impl ParsedString {
+    fn new(text: GString, node_path: &str, property_name: &str) -> Self {
+        Self {
+            text,
+            node_path: node_path.to_owned(),
+            property_name: property_name.to_owned(),
+            attributes: vec![],
+            explicit_id: None,
+            comment: None,
+        }
+    }
+}
 
 pub struct FluentPackedSceneTranslationParser {
     lookup_properties: HashSet<Gd<RegEx>>,
     exception_list: HashMap<StringName, HashSet<Gd<RegEx>>>,
+    /// Maps property names to the attribute they are grouped into, on the message of the node's `text`.
+    attribute_properties: HashMap<StringName, String>,
+    /// Node property holding an explicit message id, e.g. `metadata/fluent_id`.
+    id_metadata_property: StringName,
+    /// Node property holding a comment for translators, e.g. `metadata/fluent_comment`.
+    comment_metadata_property: StringName,
+    /// Whether the node's `editor_description` is used as comment for translators, if there is no comment metadata.
+    use_editor_description: bool,
+    resource_parser: FluentResourceTranslationParser,
+    gdscript_parser: FluentGDScriptTranslationParser,
 }
 
 /// Implementation is closely related to that of PackedSceneEditorTranslationParserPlugin.
-/// Main difference is that it does not (yet) call parsers recursively.
+/// Main difference is that it also extracts messages of instanced scenes, taking overridden properties into account.
 impl FluentTranslationParser for FluentPackedSceneTranslationParser {
     fn get_recognized_extensions(&self) -> Vec<GString> {
         ResourceLoader::singleton()
@@ -21,25 +57,127 @@ impl FluentTranslationParser for FluentPackedSceneTranslationParser {
     }
 
     fn extract_messages(&self, path: &GString) -> MessageGeneration {
-        let class_db = ClassDb::singleton();
-
         let loaded_res = ResourceLoader::singleton()
             .load_ex(path)
             .type_hint("PackedScene")
             .cache_mode(CacheMode::REUSE)
             .done();
-        if loaded_res.is_none() {
+        let Some(loaded_res) = loaded_res else {
             godot_error!("Failed loading {path}.");
             return Default::default();
+        };
+        // The recognized extensions include those of all resources (`tres`, `res`), which are extracted by the resource extractor instead.
+        // Don't assume that every such file is a scene, e.g. item or quest resources.
+        let Ok(loaded_res) = loaded_res.try_cast::<PackedScene>() else {
+            return Default::default();
+        };
+        let Some(state) = loaded_res.get_state() else {
+            godot_error!("Failed reading the scene state of {path}.");
+            return Default::default();
+        };
+
+        let mut messages = MessageGeneration::new();
+        let mut visited = vec![path.clone()];
+        self.extract_from_state(path, &state, &HashSet::new(), &mut visited, &mut messages);
+        messages
+    }
+}
+
+impl FluentPackedSceneTranslationParser {
+    pub fn init() -> Self {
+        let lookup_properties = [
+            "^text$",
+            "^.+_text$",
+            "^popup/.+/text$",
+            "^title$",
+            "^filters$", /* "^script$", */
+        ]
+            .map(|str| RegEx::create_from_string(str).unwrap())
+            .into();
+
+        let exception_list = [
+            ("LineEdit", ["^text$"]),
+            ("TextEdit", ["^text$"]),
+            ("CodeEdit", ["^text$"]),
+        ]
+            .map(|(typename, strs)| {
+                (
+                    StringName::from(typename),
+                    HashSet::from(strs.map(|str| RegEx::create_from_string(str).unwrap())),
+                )
+            })
+            .into();
+
+        let attribute_properties = Dictionary::from_variant(&ProjectSettings::singleton().get_setting(PROJECT_SETTING_GENERATOR_ATTRIBUTE_PROPERTIES))
+            .iter_shared()
+            .filter_map(|(property, attribute)| {
+                let attribute = attribute.stringify().to_string();
+                if attribute.is_empty() || FluentGenerator::make_safe_identifier(&attribute) != attribute {
+                    godot_warn!("Invalid attribute name \"{attribute}\" for property {property} in FluentGenerator settings, ignoring.");
+                    return None;
+                }
+                Some((StringName::from(&property.stringify()), attribute))
+            })
+            .collect();
+
+        let id_metadata_property = Self::metadata_property(PROJECT_SETTING_GENERATOR_ID_METADATA_KEY);
+        let comment_metadata_property = Self::metadata_property(PROJECT_SETTING_GENERATOR_COMMENT_METADATA_KEY);
+        let use_editor_description = ProjectSettings::singleton().get_setting(PROJECT_SETTING_GENERATOR_USE_EDITOR_DESCRIPTION).booleanize();
+
+        Self {
+            lookup_properties,
+            exception_list,
+            attribute_properties,
+            id_metadata_property,
+            comment_metadata_property,
+            use_editor_description,
+            resource_parser: FluentResourceTranslationParser::init(),
+            gdscript_parser: FluentGDScriptTranslationParser::init(),
         }
-        let loaded_res = loaded_res.unwrap().cast::<PackedScene>();
-        let state = loaded_res.get_state().unwrap();
+    }
 
-        let mut parsed_strings = Vec::<GString>::new();
+    /// Returns the node property of the metadata whose key is stored in the given Project Setting, or an empty name if the key is empty.
+    fn metadata_property(setting: &str) -> StringName {
+        let key = ProjectSettings::singleton().get_setting(setting).stringify();
+        if key.is_empty() {
+            StringName::default()
+        } else {
+            StringName::from(format!("metadata/{key}").as_str())
+        }
+    }
+
+    /// Extracts the messages of a scene, including the scenes it instances.
+    ///
+    /// Properties contained in `overridden` as (node path, property name) are skipped, since they are set by an instancing scene.
+    fn extract_from_state(
+        &self,
+        scene_path: &GString,
+        state: &Gd<SceneState>,
+        overridden: &HashSet<(String, StringName)>,
+        visited: &mut Vec<GString>,
+        messages: &mut MessageGeneration,
+    ) {
+        let class_db = ClassDb::singleton();
+
+        let mut parsed_strings = Vec::<ParsedString>::new();
         let mut atr_owners = Vec::<(NodePath, bool)>::new();
         let mut tabcontainer_paths = Vec::<GString>::new();
         for i in 0..state.get_node_count() {
             let mut node_type = state.get_node_type(i);
+            let node_path = Self::normalize_path(state.get_node_path(i));
+
+            // Handle instanced scenes.
+            let instance = state.get_node_instance(i);
+            if node_type.is_empty() {
+                if let Some(instance) = &instance {
+                    let _state = instance.get_state().unwrap();
+                    node_type = _state.get_node_type(0);
+                } else {
+                    // Overrides of editable children have neither, the type is defined by the owning instance's scene.
+                    node_type = Self::resolve_node_type(state, &node_path);
+                }
+            }
+
             let is_control = class_db.is_parent_class(&node_type, "Control");
 
             // Before auto_translate_mode.
@@ -53,18 +191,9 @@ impl FluentTranslationParser for FluentPackedSceneTranslationParser {
 
             let parent_path = state.get_node_path_ex(i).for_parent(true).done();
 
-            // Handle instanced scenes.
-            if node_type.is_empty() {
-                let instance = state.get_node_instance(i);
-                if let Some(instance) = instance {
-                    let _state = instance.get_state().unwrap();
-                    node_type = _state.get_node_type(0);
-                }
-            }
-
             // Find the `auto_translate_mode` property.
             let (mut auto_translating, auto_translate_mode_found) = Self::find_auto_translate_mode(
-                &state,
+                state,
                 i,
                 &mut atr_owners,
                 &parent_path.to_string(),
@@ -97,7 +226,7 @@ impl FluentTranslationParser for FluentPackedSceneTranslationParser {
                     && GString::from(parent_path)
                         == tabcontainer_paths[((tabcontainer_paths.len() as i64) - 1) as usize]
                 {
-                    parsed_strings.push(GString::from(state.get_node_name(i)));
+                    parsed_strings.push(ParsedString::new(GString::from(state.get_node_name(i)), &node_path, "name"));
                 }
             }
 
@@ -105,36 +234,68 @@ impl FluentTranslationParser for FluentPackedSceneTranslationParser {
                 continue;
             }
 
+            if let Some(instance) = &instance {
+                self.extract_from_instance(state, i, instance, overridden, visited, messages);
+            }
+
             if node_type == "TabContainer".into() {
                 tabcontainer_paths.push(GString::from(state.get_node_path(i)));
             }
 
+            let mut node_text = None;
+            // Tuples of (attribute, property name, text).
+            let mut node_attributes = Vec::<(String, String, GString)>::new();
+            let mut explicit_id = None;
+            let mut comment = None;
+            let mut editor_description = None;
+            let node_strings_start = parsed_strings.len();
             for j in 0..state.get_node_property_count(i) {
                 let property_name = state.get_node_property_name(i, j);
+                let property_value = state.get_node_property_value(i, j);
 
-                if !self.match_property(&property_name, &node_type) {
+                // Extracted from the instancing scene instead.
+                if overridden.contains(&(node_path.clone(), property_name.clone())) {
                     continue;
                 }
 
-                let property_value = state.get_node_property_value(i, j);
-                /*
-                if property_name == "script" && property_value.get_type() == Variant::OBJECT && !property_value.is_null() {
+                if property_name == "script".into() {
                     // Parse built-in script.
-                    let s = property_value.cast::<Script>();
-                    if !s.is_built_in() {
-                        continue;
+                    if let Ok(script) = property_value.try_to::<Gd<Script>>() {
+                        if script.is_class("GDScript") && FluentResourceTranslationParser::is_built_in(&script.clone().upcast()) {
+                            let script_messages = self.gdscript_parser.extract_from_source(&script.get_path().to_string(), &script.get_source_code().to_string());
+                            merge_messages(messages, script_messages);
+                        }
                     }
+                    continue;
+                }
 
-                    if let Some(extension) = s.get_language().get_extension() {
-                        if EditorTranslationParser::get_singleton().can_parse(&extension) {
-                            let (temp, ids_context_plural) = EditorTranslationParser::get_singleton()
-                                .get_parser(&extension)
-                                .parse_file(s.get_path());
-                            parsed_strings.extend(temp);
-                            r_ids_ctx_plural.extend(ids_context_plural);
-                        }
+                // Extract sub-resources embedded in the scene, e.g. custom resources assigned to exported properties.
+                if let Ok(resource) = property_value.try_to::<Gd<Resource>>() {
+                    if FluentResourceTranslationParser::is_built_in(&resource) {
+                        merge_messages(messages, self.resource_parser.extract_from_resource(&resource));
                     }
-                } else */
+                    continue;
+                }
+
+                if !self.id_metadata_property.is_empty() && property_name == self.id_metadata_property {
+                    explicit_id = Some(property_value.stringify().to_string()).filter(|id| !id.is_empty());
+                    continue;
+                }
+
+                if !self.comment_metadata_property.is_empty() && property_name == self.comment_metadata_property {
+                    comment = Some(property_value.stringify().to_string()).filter(|comment| !comment.trim().is_empty());
+                    continue;
+                }
+
+                if self.use_editor_description && property_name == "editor_description".into() {
+                    editor_description = Some(property_value.stringify().to_string()).filter(|description| !description.trim().is_empty());
+                    continue;
+                }
+
+                if !self.match_property(&property_name, &node_type) {
+                    continue;
+                }
+
                 if node_type == "FileDialog".into() && property_name == "filters".into() {
                     // Extract FileDialog's filters property with values in format "*.png ; PNG Images","*.gd ; GDScript Files".
                     let str_values = PackedStringArray::from_variant(&property_value);
@@ -142,55 +303,153 @@ impl FluentTranslationParser for FluentPackedSceneTranslationParser {
                         let str_value = str_value.to_string();
                         let desc = str_value.split_once(';').unwrap_or(("", "")).1.trim();
                         if !desc.is_empty() {
-                            parsed_strings.push(GString::from(desc));
+                            parsed_strings.push(ParsedString::new(GString::from(desc), &node_path, "filters"));
                         }
                     }
                 } else if property_value.get_type() == VariantType::STRING {
                     // Prevent reading text containing only spaces.
                     let str_value = GString::from_variant(&property_value);
                     if !str_value.to_string().trim().is_empty() {
-                        parsed_strings.push(str_value);
+                        match self.attribute_properties.get(&property_name) {
+                            Some(attribute) => node_attributes.push((attribute.clone(), property_name.to_string(), str_value)),
+                            None if property_name == "text".into() => node_text = Some(str_value),
+                            None => parsed_strings.push(ParsedString::new(str_value, &node_path, &property_name.to_string())),
+                        }
                     }
                 }
             }
+
+            // Group attributes with the node's text, or extract them as separate messages if there is none.
+            // The explicit id applies to the node's text.
+            match node_text {
+                Some(text) => parsed_strings.push(ParsedString {
+                    attributes: node_attributes.into_iter().map(|(attribute, _, value)| (attribute, value)).collect(),
+                    explicit_id,
+                    ..ParsedString::new(text, &node_path, "text")
+                }),
+                None => parsed_strings.extend(
+                    node_attributes.into_iter().map(|(_, property_name, value)| ParsedString::new(value, &node_path, &property_name)),
+                ),
+            }
+
+            // The comment applies to all texts of the node.
+            if let Some(comment) = comment.or(editor_description) {
+                for parsed in &mut parsed_strings[node_strings_start..] {
+                    parsed.comment = Some(comment.clone());
+                }
+            }
         }
 
-        // Assume that ids = messages.
-        parsed_strings
-            .into_iter()
-            .map(|string| (string.to_string(), string.to_string()))
-            .collect()
+        // Keyed by their context, the generator uses the text as id unless it derives another one.
+        let scene_name = PathBuf::from(scene_path.to_string()).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        for parsed in parsed_strings {
+            let text = parsed.text.to_string();
+            let id_path = match parsed.node_path.as_str() {
+                "." => format!("{scene_name}/{}", parsed.property_name),
+                node_path => format!("{scene_name}/{node_path}/{}", parsed.property_name),
+            };
+            let mut message = ExtractedMessage::from_text(&text)
+                .with_reference(format!("{scene_path}:{}", parsed.node_path))
+                .with_id_context(&text, id_path, parsed.explicit_id);
+            for (attribute, value) in parsed.attributes {
+                message = message.with_attribute(attribute, &value.to_string());
+            }
+            if let Some(comment) = &parsed.comment {
+                message = message.with_comment(comment);
+            }
+            insert_message(messages, message.context_key().unwrap_or(text), message);
+        }
     }
-}
 
-impl FluentPackedSceneTranslationParser {
-    pub fn init() -> Self {
-        let lookup_properties = [
-            "^text$",
-            "^.+_text$",
-            "^popup/.+/text$",
-            "^title$",
-            "^filters$", /* "^script$", */
-        ]
-            .map(|str| RegEx::create_from_string(str).unwrap())
-            .into();
+    /// Extracts the messages of an instanced scene.
+    /// Properties set on the instance take precedence over those of the instanced scene, and are extracted from the instancing scene.
+    fn extract_from_instance(
+        &self,
+        state: &Gd<SceneState>,
+        instance_node: i32,
+        instance: &Gd<PackedScene>,
+        overridden: &HashSet<(String, StringName)>,
+        visited: &mut Vec<GString>,
+        messages: &mut MessageGeneration,
+    ) {
+        // Prevent infinite recursion on cyclic instances.
+        let instance_path = instance.get_path();
+        if visited.contains(&instance_path) {
+            return;
+        }
+        let Some(instance_state) = instance.get_state() else {
+            return;
+        };
+
+        // Collect properties overridden by this scene and by the scenes instancing it, relative to the instance root.
+        let root_path = Self::normalize_path(state.get_node_path(instance_node));
+        let mut instance_overridden = HashSet::new();
+        for (path, property_name) in overridden {
+            if let Some(relative_path) = Self::relative_path(&root_path, path) {
+                instance_overridden.insert((relative_path, property_name.clone()));
+            }
+        }
+        for i in 0..state.get_node_count() {
+            let Some(relative_path) = Self::relative_path(&root_path, &Self::normalize_path(state.get_node_path(i))) else {
+                continue;
+            };
+            for j in 0..state.get_node_property_count(i) {
+                instance_overridden.insert((relative_path.clone(), state.get_node_property_name(i, j)));
+            }
+        }
 
-        let exception_list = [
-            ("LineEdit", ["^text$"]),
-            ("TextEdit", ["^text$"]),
-            ("CodeEdit", ["^text$"]),
-        ]
-            .map(|(typename, strs)| {
-                (
-                    StringName::from(typename),
-                    HashSet::from(strs.map(|str| RegEx::create_from_string(str).unwrap())),
-                )
+        visited.push(instance_path.clone());
+        self.extract_from_state(&instance_path, &instance_state, &instance_overridden, visited, messages);
+        visited.pop();
+    }
+
+    /// Returns the type of a node inside of an instanced scene, e.g. an editable child, by looking it up in the scene of the closest instancing ancestor.
+    /// Returns an empty name if it can't be resolved.
+    fn resolve_node_type(state: &Gd<SceneState>, node_path: &str) -> StringName {
+        // The closest ancestor is the one with the longest path.
+        let owner = (0..state.get_node_count())
+            .filter_map(|i| {
+                let instance = state.get_node_instance(i)?;
+                let root_path = Self::normalize_path(state.get_node_path(i));
+                let relative_path = Self::relative_path(&root_path, node_path).filter(|path| path != ".")?;
+                Some((root_path.len(), instance, relative_path))
             })
-            .into();
+            .max_by_key(|(root_path_len, _, _)| *root_path_len);
+        let Some((_, instance, relative_path)) = owner else {
+            return StringName::default();
+        };
+        let Some(instance_state) = instance.get_state() else {
+            return StringName::default();
+        };
+
+        let node = (0..instance_state.get_node_count()).find(|&i| Self::normalize_path(instance_state.get_node_path(i)) == relative_path);
+        match node {
+            Some(i) if !instance_state.get_node_type(i).is_empty() => instance_state.get_node_type(i),
+            Some(i) => match instance_state.get_node_instance(i).and_then(|instance| instance.get_state()) {
+                Some(nested_state) => nested_state.get_node_type(0),
+                None => Self::resolve_node_type(&instance_state, &relative_path),
+            },
+            // Inside of a scene instanced by the instanced scene.
+            None => Self::resolve_node_type(&instance_state, &relative_path),
+        }
+    }
 
-        Self {
-            lookup_properties,
-            exception_list,
+    /// Returns the path of a node relative to the given root node, if it is inside of it.
+    fn relative_path(root_path: &str, path: &str) -> Option<String> {
+        if path == root_path {
+            Some(".".to_owned())
+        } else if root_path == "." {
+            Some(path.to_owned())
+        } else {
+            path.strip_prefix(&format!("{root_path}/")).map(str::to_owned)
+        }
+    }
+
+    fn normalize_path(path: NodePath) -> String {
+        let path = path.to_string();
+        match path.strip_prefix("./") {
+            Some(path) => path.to_owned(),
+            None => path,
         }
     }
 
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use godot::classes::resource_loader::CacheMode;
+use godot::classes::{ProjectSettings, RegEx, ResourceLoader};
+use godot::global::PropertyUsageFlags;
+use godot::prelude::*;
+
+use super::project_settings::*;
+use super::{insert_message, ExtractedMessage, FluentTranslationParser, MessageGeneration};
+
+/// Extracts messages from the String properties of resource files, including their embedded sub-resources.
+///
+/// Which properties are extracted is configured via the `generator/resource_include_properties`
+/// and `generator/resource_exclude_properties` Project Settings.
+pub struct FluentResourceTranslationParser {
+    include_properties: Vec<Gd<RegEx>>,
+    exclude_properties: Vec<Gd<RegEx>>,
+}
+
+impl FluentTranslationParser for FluentResourceTranslationParser {
+    fn get_recognized_extensions(&self) -> Vec<GString> {
+        vec!["tres".into(), "res".into()]
+    }
+
+    fn extract_messages(&self, path: &GString) -> MessageGeneration {
+        let loaded_res = ResourceLoader::singleton()
+            .load_ex(path)
+            .cache_mode(CacheMode::REUSE)
+            .done();
+        match loaded_res {
+            Some(resource) => self.extract_from_resource(&resource),
+            None => {
+                godot_error!("Failed loading {path}.");
+                Default::default()
+            },
+        }
+    }
+}
+
+impl FluentResourceTranslationParser {
+    pub fn init() -> Self {
+        let project_settings = ProjectSettings::singleton();
+        Self {
+            include_properties: Self::get_regex_setting(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_RESOURCE_INCLUDE_PROPERTIES)),
+            exclude_properties: Self::get_regex_setting(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_RESOURCE_EXCLUDE_PROPERTIES)),
+        }
+    }
+
+    /// Extracts messages of the given resource and all of its built-in sub-resources.
+    pub fn extract_from_resource(&self, resource: &Gd<Resource>) -> MessageGeneration {
+        let mut messages = MessageGeneration::new();
+        let mut visited = HashSet::new();
+        self.walk_resource(resource, &mut messages, &mut visited);
+        messages
+    }
+
+    /// Whether the resource is saved inside of another resource or scene file, instead of its own file.
+    pub fn is_built_in(resource: &Gd<Resource>) -> bool {
+        let path = resource.get_path().to_string();
+        path.is_empty() || path.contains("::")
+    }
+
+    fn walk_resource(&self, resource: &Gd<Resource>, messages: &mut MessageGeneration, visited: &mut HashSet<InstanceId>) {
+        // Prevent infinite recursion on cyclic references.
+        if !visited.insert(resource.instance_id()) {
+            return;
+        }
+
+        let resource_path = resource.get_path();
+        let storage = PropertyUsageFlags::STORAGE.ord() as i64;
+        for property in resource.get_property_list().iter_shared() {
+            let usage = property.get("usage").map_or(0, |usage| i64::from_variant(&usage));
+            if usage & storage == 0 {
+                continue;
+            }
+
+            let name = property.get("name").map(|name| StringName::from_variant(&name)).unwrap_or_default();
+            let value = resource.get(&name);
+            self.walk_value(&resource_path, &name, &value, messages, visited);
+        }
+    }
+
+    fn walk_value(
+        &self,
+        resource_path: &GString,
+        property_name: &StringName,
+        value: &Variant,
+        messages: &mut MessageGeneration,
+        visited: &mut HashSet<InstanceId>,
+    ) {
+        match value.get_type() {
+            VariantType::STRING => {
+                if !self.match_property(property_name) {
+                    return;
+                }
+                // Prevent reading text containing only spaces.
+                let text = GString::from_variant(value).to_string();
+                if !text.trim().is_empty() {
+                    let resource_name = PathBuf::from(resource_path.to_string()).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+                    let message = ExtractedMessage::from_text(&text)
+                        .with_reference(format!("{resource_path}:{property_name}"))
+                        .with_id_context(&text, format!("{resource_name}/{property_name}"), None);
+                    insert_message(messages, message.context_key().unwrap_or(text), message);
+                }
+            },
+            VariantType::OBJECT => {
+                // Sub-resources saved in their own file are extracted on their own.
+                if let Ok(resource) = value.try_to::<Gd<Resource>>() {
+                    if Self::is_built_in(&resource) {
+                        self.walk_resource(&resource, messages, visited);
+                    }
+                }
+            },
+            VariantType::ARRAY => {
+                for element in VariantArray::from_variant(value).iter_shared() {
+                    self.walk_value(resource_path, property_name, &element, messages, visited);
+                }
+            },
+            VariantType::PACKED_STRING_ARRAY => {
+                for element in PackedStringArray::from_variant(value).as_slice() {
+                    self.walk_value(resource_path, property_name, &element.to_variant(), messages, visited);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn match_property(&self, property_name: &StringName) -> bool {
+        let property_name = GString::from(property_name);
+        self.include_properties.iter().any(|regex| regex.search(&property_name).is_some())
+            && !self.exclude_properties.iter().any(|regex| regex.search(&property_name).is_some())
+    }
+
+    fn get_regex_setting(value: &Variant) -> Vec<Gd<RegEx>> {
+        PackedStringArray::from_variant(value)
+            .as_slice()
+            .iter()
+            .filter_map(|pattern| {
+                let regex = RegEx::create_from_string(pattern);
+                if regex.is_none() {
+                    godot_warn!("Invalid property regex \"{pattern}\" in FluentGenerator settings, ignoring.");
+                }
+                regex
+            })
+            .collect()
+    }
+}
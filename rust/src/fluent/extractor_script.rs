@@ -0,0 +1,100 @@
+use godot::prelude::*;
+
+use super::{insert_message, ExtractedMessage, FluentTranslationParser, MessageGeneration};
+
+/// Extractor defined in a script, registered via [super::FluentGenerator::add_extractor] or [super::FluentGenerator::add_extractor_callables].
+pub struct FluentScriptTranslationParser {
+    get_recognized_extensions: Callable,
+    extract_messages: Callable,
+    // Keeps a RefCounted extractor alive, as callables do not hold a reference to their object.
+    _extractor: Option<Gd<Object>>,
+}
+
+impl FluentTranslationParser for FluentScriptTranslationParser {
+    fn get_recognized_extensions(&self) -> Vec<GString> {
+        let extensions = self.get_recognized_extensions.callv(&VariantArray::new());
+        match variant_to_strings(&extensions) {
+            Some(extensions) => extensions.into_iter().map(GString::from).collect(),
+            None => {
+                godot_error!("FluentGenerator extractor's get_recognized_extensions must return a PackedStringArray, but returned {extensions}.");
+                vec![]
+            },
+        }
+    }
+
+    fn extract_messages(&self, path: &GString) -> MessageGeneration {
+        let messages = self.extract_messages.callv(&varray![path.clone()]);
+        let messages = match messages.try_to::<Dictionary>() {
+            Ok(messages) => messages,
+            Err(_) => {
+                godot_error!("FluentGenerator extractor's extract_messages must return a Dictionary, but returned {messages} for {path}.");
+                return Default::default();
+            },
+        };
+
+        let mut result = MessageGeneration::new();
+        for (id, message) in messages.iter_shared() {
+            let id = id.stringify().to_string();
+            match ExtractedMessage::try_from_variant(&id, &message) {
+                Some(mut message) => {
+                    // Default to referencing the file itself.
+                    if message.references.is_empty() {
+                        message.references.push(path.to_string());
+                    }
+                    insert_message(&mut result, id, message);
+                },
+                None => {
+                    godot_warn!("FluentGenerator extractor returned invalid message {message} for \"{id}\" in {path}, ignoring.");
+                },
+            }
+        }
+        result
+    }
+
+    fn is_cacheable(&self) -> bool {
+        // The script may have changed, or depend on anything else.
+        false
+    }
+}
+
+impl FluentScriptTranslationParser {
+    pub fn from_object(extractor: Gd<Object>) -> Option<Self> {
+        for method in ["get_recognized_extensions", "extract_messages"] {
+            if !extractor.has_method(method) {
+                godot_error!("FluentGenerator extractor {extractor} does not implement {method}.");
+                return None;
+            }
+        }
+
+        Some(Self {
+            get_recognized_extensions: Callable::from_object_method(&extractor, "get_recognized_extensions"),
+            extract_messages: Callable::from_object_method(&extractor, "extract_messages"),
+            _extractor: Some(extractor),
+        })
+    }
+
+    pub fn from_callables(get_recognized_extensions: Callable, extract_messages: Callable) -> Option<Self> {
+        if !get_recognized_extensions.is_valid() || !extract_messages.is_valid() {
+            godot_error!("FluentGenerator extractor callables must be valid.");
+            return None;
+        }
+
+        Some(Self {
+            get_recognized_extensions,
+            extract_messages,
+            _extractor: None,
+        })
+    }
+}
+
+/// Converts a PackedStringArray or an Array of Strings.
+pub(crate) fn variant_to_strings(value: &Variant) -> Option<Vec<String>> {
+    match value.get_type() {
+        VariantType::PACKED_STRING_ARRAY => Some(PackedStringArray::from_variant(value).as_slice().iter().map(GString::to_string).collect()),
+        VariantType::ARRAY => VariantArray::from_variant(value)
+            .iter_shared()
+            .map(|element| element.try_to::<GString>().ok().map(|element| element.to_string()))
+            .collect(),
+        _ => None,
+    }
+}
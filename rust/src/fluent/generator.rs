@@ -1,109 +1,702 @@
 use godot::prelude::*;
-use godot::classes::{FileAccess, ProjectSettings, RegEx, RegExMatch};
+use godot::classes::{FileAccess, ProjectSettings, RegEx, RegExMatch, ResourceLoader, Script};
 use godot::global::error_string;
 use itertools::Itertools;
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque}, path::PathBuf};
 use fluent_syntax::{ast, parser::parse};
 use fluent_syntax::serializer::serialize;
 
-use crate::utils::{create_or_open_file_for_read_write, get_files_recursive};
+use crate::utils::{create_or_open_file_for_read_write, get_file_paths_recursive};
 use godot::global::Error as GdErr;
 
-use super::{project_settings::{INVALID_MESSAGE_HANDLING_SKIP, PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING, PROJECT_SETTING_GENERATOR_LOCALES, PROJECT_SETTING_GENERATOR_PATTERNS}, FluentPackedSceneTranslationParser, FluentTranslationParser};
+use super::{insert_message, merge_messages, message_id, pattern, project_settings::*, ExtractedMessage, FluentGeneratorConfig, GeneratorCache, IdContext, FluentCSharpTranslationParser, FluentGDScriptTranslationParser, FluentPackedSceneTranslationParser, FluentResourceTranslationParser, FluentScriptTranslationParser, FluentTranslationParser};
+
+/// Comment line added to messages which are no longer found in any source file.
+const STALE_MESSAGE_COMMENT: &str = "UNUSED";
+/// Prefix of comment lines which reference the source of a message.
+const SOURCE_REFERENCE_COMMENT_PREFIX: &str = "Source: ";
+/// Prefix of comment lines which contain the source text of a message in a target locale file.
+const SOURCE_TEXT_COMMENT_PREFIX: &str = "Source text: ";
+/// Comment line added to messages of target locale files which need to be translated.
+const TODO_COMMENT: &str = "TODO: translate";
+/// Prefix of the comment lines which contain comments for translators, e.g. from node metadata.
+const TRANSLATOR_COMMENT_PREFIX: &str = "Note: ";
+/// Prefix of the comment line which contains the hash of the source-locale message, to detect changes of the source text.
+const SOURCE_HASH_COMMENT_PREFIX: &str = "Source-Hash: ";
+/// Comment line added to messages whose source-locale message changed since they were translated.
+const FUZZY_COMMENT: &str = "fuzzy";
+/// Content of the group comment which stale messages are moved below.
+const OBSOLETE_GROUP_COMMENT: &str = "Obsolete";
 
 /// Allows generating Fluent Translation List (FTL) files by extracting keys.
 /// 
-/// For now, this class only supports [PackedScene] files and is completely loaded via Project Settings configuration.
-/// It may be updated in the future to receive a proper API and editor integration.
+/// Out of the box, this class supports [PackedScene], [Resource], GDScript and C# files. Additional extractors can be registered via [method add_extractor].
+/// It is configured via Project Settings, or a [FluentGeneratorConfig] (see [method create_from]).
+/// In the editor, generation is also available from the Fluent dock (with a profile selector and a dry run) and the "Generate Fluent Translations" tool menu item.
+/// From scripts, use [method generate] or [method generate_dry_run], and [method list_untranslated] or [method list_outdated] to inspect translation progress.
 #[derive(GodotClass)]
 #[class(no_init)]
 pub struct FluentGenerator {
     locales: Vec<String>,
-    file_patterns: HashMap<Gd<RegEx>, String>,
+    // In the order of the Project Setting, so that the first matching pattern is deterministic.
+    file_patterns: Vec<(Gd<RegEx>, String)>,
+    exclude_patterns: Vec<Gd<RegEx>>,
     invalid_message_handling: i32,
-    // TODO: Once we have a dynamic plugin system, turn into a Vec<_> and loop through ALL parsers (don't stop after the first! merge results!)
-    extractor: FluentPackedSceneTranslationParser,
+    stale_message_handling: i32,
+    source_references: bool,
+    id_strategy: i32,
+    id_max_length: usize,
+    source_locale: String,
+    /// Whether changes of source-locale messages are tracked, marking their translations as `fuzzy`.
+    /// Requires the source locale to be set explicitly and to be one of the generated locales.
+    track_changes: bool,
+    target_seeding: i32,
+    convert_placeholders: bool,
+    use_cache: bool,
+    /// Path of the [FluentGeneratorConfig] this was created from, empty for the Project Settings. Each profile has its own cache.
+    profile_path: String,
+    // All parsers are run for a file (don't stop after the first! merge results!)
+    extractors: Vec<Box<dyn FluentTranslationParser>>,
 }
 
 /// Uses a HashMap<id, msg> to disallow duplicate messages to be generated.
-pub type MessageGeneration = HashMap<String, String>;
+pub type MessageGeneration = HashMap<String, ExtractedMessage>;
+
+/// Messages to be written to a single FTL file.
+#[derive(Default)]
+struct GenerateTask {
+    messages: MessageGeneration,
+    renamed: BTreeMap<String, String>,
+    skipped: BTreeSet<String>,
+    collisions: BTreeMap<String, BTreeSet<String>>,
+    placeholders: BTreeMap<String, BTreeMap<String, String>>,
+    /// Files not depending on the locale are assumed to be in the source locale.
+    is_source_locale: bool,
+    /// Path of the FTL file of the source locale, for files in other locales.
+    source_locale_path: Option<String>,
+    /// Source file each message was first found in, which new messages are grouped by.
+    sources: HashMap<String, String>,
+    /// Source text of each derived id, to detect different texts resulting in the same id.
+    derived_ids: HashMap<String, String>,
+}
+
+impl GenerateTask {
+    /// Returns the id to use for a derived id, appending a suffix if it is already used for a different text.
+    /// Which text keeps the plain id is decided once all texts are known, see [Self::resolve_plain_ids].
+    fn resolve_collision(&mut self, id: String, text: &str) -> String {
+        let mut candidate = id.clone();
+        while let Some(existing_text) = self.derived_ids.get(&candidate) {
+            if existing_text == text {
+                return candidate;
+            }
+            let collisions = self.collisions.entry(id.clone()).or_default();
+            collisions.insert(existing_text.clone());
+            collisions.insert(text.to_owned());
+            candidate = message_id::disambiguate(&candidate, text);
+        }
+        self.derived_ids.insert(candidate.clone(), text.to_owned());
+        candidate
+    }
+
+    /// Gives the plain id of each collision to the text whose message already uses it in the source-locale file,
+    /// and a suffixed id to all other texts, so that an existing id is never reassigned to a different text.
+    /// If the plain id is not used yet, the text found first keeps it. If it is used by a text that no longer exists, all texts are suffixed.
+    ///
+    /// `existing` maps the ids of the existing source-locale messages to the FTL source of their value.
+    fn resolve_plain_ids(&mut self, existing: &HashMap<String, String>) {
+        for id in self.collisions.keys().cloned().collect_vec() {
+            let Some(first_text) = self.derived_ids.get(&id).cloned() else {
+                continue;
+            };
+            let existing_value = existing.get(&id);
+            let owner = self.derived_ids
+                .iter()
+                .filter(|(candidate, text)| **candidate == id || **candidate == message_id::disambiguate(&id, text))
+                .find(|(candidate, _)| {
+                    let value = self.messages.get(*candidate).map(|message| FluentGenerator::pattern_to_source(&message.value));
+                    value.is_some() && value.as_ref() == existing_value
+                })
+                .map(|(candidate, text)| (candidate.clone(), text.clone()));
+            let keeps_plain_id = match &owner {
+                Some((_, text)) => *text == first_text,
+                None => existing_value.is_none(),
+            };
+            if keeps_plain_id {
+                continue;
+            }
+
+            self.rename(&id, &message_id::disambiguate(&id, &first_text));
+            if let Some((candidate, _)) = owner {
+                self.rename(&candidate, &id);
+            }
+        }
+    }
+
+    fn rename(&mut self, from: &str, to: &str) {
+        if let Some(message) = self.messages.remove(from) {
+            self.messages.insert(to.to_owned(), message);
+        }
+        if let Some(source) = self.sources.remove(from) {
+            self.sources.insert(to.to_owned(), source);
+        }
+        if let Some(text) = self.derived_ids.remove(from) {
+            self.derived_ids.insert(to.to_owned(), text);
+        }
+        let attribute_prefix = format!("{from}.");
+        let placeholder_keys = self.placeholders
+            .keys()
+            .filter(|key| *key == from || key.starts_with(&attribute_prefix))
+            .cloned()
+            .collect_vec();
+        for key in placeholder_keys {
+            let variables = self.placeholders.remove(&key).unwrap_or_default();
+            self.placeholders.insert(format!("{to}{}", &key[from.len()..]), variables);
+        }
+    }
+}
+
+/// A generation in progress, see [FluentGenerator::start_run].
+pub(crate) struct GenerationRun {
+    dry_run: bool,
+    /// FTL files to generate, if not all.
+    targets: Option<HashSet<String>>,
+    files: VecDeque<(Gd<RegExMatch>, String)>,
+    file_count: usize,
+    cache: Option<GeneratorCache>,
+    tasks: BTreeMap<String, GenerateTask>,
+    /// Set once all source files are extracted.
+    writes: Option<VecDeque<(String, GenerateTask)>>,
+    write_count: usize,
+    /// Hashes of the messages of each source-locale FTL file, to detect outdated translations.
+    source_hashes: HashMap<String, HashMap<String, String>>,
+    /// Same as returned by [FluentGenerator::generate].
+    pub report: Dictionary,
+}
+
+/// A file processed by [FluentGenerator::step_run].
+pub(crate) struct GenerationStep {
+    /// Whether an FTL file was written, instead of a source file extracted.
+    pub is_writing: bool,
+    pub path: String,
+    /// 1-based index of the file, out of `count` files of the same kind.
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Changes made (or, for a dry run, to be made) to a single FTL file.
+#[derive(Default)]
+struct GenerationReport {
+    added: BTreeSet<String>,
+    unchanged: BTreeSet<String>,
+    renamed: BTreeMap<String, String>,
+    skipped: BTreeSet<String>,
+    stale: BTreeSet<String>,
+    collisions: BTreeMap<String, BTreeSet<String>>,
+    placeholders: BTreeMap<String, BTreeMap<String, String>>,
+    updated: BTreeSet<String>,
+    outdated: BTreeSet<String>,
+    /// Hash of each message in the resulting file, see [FluentGenerator::get_source_hashes].
+    source_hashes: HashMap<String, String>,
+}
+
+impl GenerationReport {
+    fn to_dictionary(&self) -> Dictionary {
+        let to_array = |ids: &BTreeSet<String>| ids.iter().map(|id| GString::from(id.as_str())).collect::<PackedStringArray>();
+        let mut renamed = Dictionary::new();
+        for (id, safe_id) in &self.renamed {
+            renamed.set(GString::from(id.as_str()), GString::from(safe_id.as_str()));
+        }
+
+        let mut dictionary = Dictionary::new();
+        dictionary.set("added", to_array(&self.added));
+        dictionary.set("unchanged", to_array(&self.unchanged));
+        dictionary.set("renamed", renamed);
+        dictionary.set("skipped", to_array(&self.skipped));
+        dictionary.set("stale", to_array(&self.stale));
+        dictionary.set("updated", to_array(&self.updated));
+        dictionary.set("outdated", to_array(&self.outdated));
+        let mut collisions = Dictionary::new();
+        for (id, texts) in &self.collisions {
+            collisions.set(GString::from(id.as_str()), to_array(texts));
+        }
+        dictionary.set("collisions", collisions);
+        let mut placeholders = Dictionary::new();
+        for (id, variables) in &self.placeholders {
+            let mut mapping = Dictionary::new();
+            for (variable, placeholder) in variables {
+                mapping.set(GString::from(variable.as_str()), GString::from(placeholder.as_str()));
+            }
+            placeholders.set(GString::from(id.as_str()), mapping);
+        }
+        dictionary.set("placeholders", placeholders);
+        dictionary
+    }
+}
 
 #[godot_api]
 impl FluentGenerator {
     /// Create a new [FluentGenerator] instance using the Project Settings for configuration.
     #[func]
     pub fn create() -> Gd<Self> {
-        let project_settings = ProjectSettings::singleton();
-        let locales = PackedStringArray::from_variant(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_LOCALES));
-        let locales = locales.as_slice().iter().map(|s| s.to_string()).collect();
-        let file_patterns = Dictionary::from_variant(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_PATTERNS))
+        Self::create_from(FluentGeneratorConfig::from_project_settings())
+    }
+
+    /// Create a new [FluentGenerator] instance using the given configuration, e.g. one of several profiles saved as resources.
+    #[func]
+    pub fn create_from(config: Gd<FluentGeneratorConfig>) -> Gd<Self> {
+        let profile_path = config.upcast_ref::<Resource>().get_path().to_string();
+        let config = config.bind();
+        let locales = config.locales.as_slice().iter().map(|s| s.to_string()).collect();
+        let file_patterns = config.file_patterns
             .iter_shared()
             .map(|(k, v)| {
                 let k = GString::from_variant(&k);
+                if !k.to_string().contains('/') {
+                    godot_warn!("File pattern \"{k}\" in FluentGenerator settings is matched against the full path (e.g. `^res://(.+)\\.tscn$`), but contains no `/`. Its capture groups include the folders of the file.");
+                }
                 let k = RegEx::create_from_string(&k).unwrap();
                 let v = GString::from_variant(&v).to_string();
                 (k, v)
             })
             .collect();
-        Gd::from_object(Self {
+        let exclude_patterns = config.exclude_patterns
+            .as_slice()
+            .iter()
+            .filter_map(|pattern| {
+                let regex = RegEx::create_from_string(pattern);
+                if regex.is_none() {
+                    godot_warn!("Invalid exclude regex \"{pattern}\" in FluentGenerator settings, ignoring.");
+                }
+                regex
+            })
+            .collect();
+        let mut source_locale = config.source_locale.to_string();
+        let track_changes = !source_locale.is_empty() && config.locales.as_slice().contains(&config.source_locale);
+        if !source_locale.is_empty() && !track_changes {
+            godot_warn!("Source locale \"{source_locale}\" is not one of the locales in FluentGenerator settings, changes of source texts are not tracked.");
+        }
+        if source_locale.is_empty() {
+            source_locale = ProjectSettings::singleton().get_setting(PROJECT_SETTING_FALLBACK_LOCALE).stringify().to_string();
+        }
+        let mut generator = Gd::from_object(Self {
             locales,
             file_patterns,
-            invalid_message_handling: i32::from_variant(&project_settings.get_setting(PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING)),
-            extractor: FluentPackedSceneTranslationParser::init(),
-        })
+            exclude_patterns,
+            invalid_message_handling: config.invalid_message_handling,
+            stale_message_handling: config.stale_message_handling,
+            source_references: config.source_references,
+            id_strategy: config.id_strategy,
+            id_max_length: config.id_max_length.max(0) as usize,
+            source_locale,
+            track_changes,
+            target_seeding: config.target_seeding,
+            convert_placeholders: config.convert_placeholders,
+            use_cache: config.use_cache,
+            profile_path,
+            extractors: vec![
+                Box::new(FluentPackedSceneTranslationParser::init()),
+                Box::new(FluentGDScriptTranslationParser::init()),
+                Box::new(FluentCSharpTranslationParser::init()),
+                Box::new(FluentResourceTranslationParser::init()),
+            ],
+        });
+
+        for path in config.extractor_scripts.as_slice() {
+            if let Some(extractor) = Self::instantiate_extractor_script(path) {
+                generator.bind_mut().add_extractor(extractor);
+            }
+        }
+        generator
+    }
+
+    /// Returns the paths of the [FluentGeneratorConfig] resources listed in the `generator/profiles` Project Setting.
+    pub(crate) fn get_profiles() -> PackedStringArray {
+        PackedStringArray::from_variant(&ProjectSettings::singleton().get_setting(PROJECT_SETTING_GENERATOR_PROFILES))
+    }
+
+    /// Creates a generator for the profile saved at `path` (see [FluentGenerator::get_profiles]), or for the Project Settings if None.
+    pub(crate) fn create_for_profile(path: Option<&GString>) -> Option<Gd<Self>> {
+        let Some(path) = path else {
+            return Some(Self::create());
+        };
+        let config = ResourceLoader::singleton().load(path).and_then(|config| config.try_cast::<FluentGeneratorConfig>().ok());
+        if config.is_none() {
+            godot_error!("Unable to load FluentGenerator profile {path}.");
+        }
+        config.map(Self::create_from)
+    }
+
+    /// Registers an additional extractor, which is run for all files with one of its recognized extensions.
+    /// Results of all extractors matching a file are merged.
+    ///
+    /// [param extractor] must implement `get_recognized_extensions() -> PackedStringArray` and `extract_messages(path: String) -> Dictionary`.
+    /// The returned Dictionary maps message identifiers to either the message text, or a Dictionary with the optional keys:
+    /// - `text: String`: the message text, defaults to the identifier.
+    /// - `variables: PackedStringArray`: names of the variables used in the message.
+    /// - `references: PackedStringArray`: source locations written as `Source:` comments (when `generator/source_references` is enabled).
+    /// - `comment: String`: a comment for translators, written as a `Note:` comment.
+    #[func]
+    pub fn add_extractor(&mut self, extractor: Gd<Object>) -> GdErr {
+        match FluentScriptTranslationParser::from_object(extractor) {
+            Some(extractor) => {
+                self.extractors.push(Box::new(extractor));
+                GdErr::OK
+            },
+            None => GdErr::ERR_INVALID_PARAMETER,
+        }
+    }
+
+    /// Same as [method add_extractor], but using a callable for each of the two methods.
+    #[func]
+    pub fn add_extractor_callables(&mut self, get_recognized_extensions: Callable, extract_messages: Callable) -> GdErr {
+        match FluentScriptTranslationParser::from_callables(get_recognized_extensions, extract_messages) {
+            Some(extractor) => {
+                self.extractors.push(Box::new(extractor));
+                GdErr::OK
+            },
+            None => GdErr::ERR_INVALID_PARAMETER,
+        }
     }
 
     /// Generate Fluent Translation List (FTL) files, creating or updating files as necessary.
-    /// If a message is already translated, it will not be updated.
-    /// Deleted keys are currently left untouched and must be manually purged.
+    /// If a message is already translated, it will not be updated. If `generator/source_locale` is set, messages of the source locale are updated when their source text changed,
+    /// unless they were edited manually, and the corresponding messages of other locales are marked with a `# fuzzy` comment (see [method list_outdated]).
+    /// Messages which no longer exist in any source file are handled according to the `generator/stale_message_handling` Project Setting.
+    ///
+    /// Returns a report Dictionary, which maps each target FTL path to a Dictionary with the keys
+    /// `added`, `unchanged`, `skipped`, `stale`, `updated` (source-locale messages whose text changed), `outdated` (messages marked as `fuzzy`)
+    /// (each a sorted [PackedStringArray] of message identifiers),
+    /// `renamed` (a Dictionary mapping invalid identifiers to the valid identifiers they were converted to)
+    /// `collisions` (a Dictionary mapping derived identifiers to the different source texts that resulted in them)
+    /// and `placeholders` (a Dictionary mapping message identifiers, or `identifier.attribute`, to a Dictionary mapping each variable to the placeholder it replaced).
     #[func]
-    pub fn generate(&self) {
-        // Collect source files and batched write operations.
-        let files = self.get_matching_files();
-        let mut generate_tasks = HashMap::<String, MessageGeneration>::new();
-        for (source, pattern) in files {
-            let targets = self.apply_pattern(source.clone(), &pattern);
-            for target in targets {
-                let entry = generate_tasks.entry(target.clone());
-                let mut messages = self.get_messages(&source.get_string())
-                    .into_iter()
-                    .filter_map(|(id, message)| {
-                        if id.is_empty() {
-                            return None;
-                        }
+    pub fn generate(&self) -> Dictionary {
+        self.run(false)
+    }
 
-                        let safe_id = Self::make_safe_identifier(&id);
-                        if id != safe_id {
-                            if self.invalid_message_handling == INVALID_MESSAGE_HANDLING_SKIP {
-                                // Skip invalid message.
-                                return None;
-                            } else {
-                                return Some((safe_id, message));
-                            }
-                        }
+    /// Same as [method generate], but only computes the report without writing any files.
+    #[func]
+    pub fn generate_dry_run(&self) -> Dictionary {
+        self.run(true)
+    }
+
+    /// Lists the messages of the given target locale that still need to be translated,
+    /// i.e. have an empty value or attribute, or a `# TODO: translate` comment (see the `generator/target_seeding` Project Setting).
+    ///
+    /// Returns a Dictionary mapping each existing FTL file of the locale to a [PackedStringArray] of message identifiers.
+    #[func]
+    pub fn list_untranslated(&self, locale: GString) -> Dictionary {
+        self.list_messages(&locale.to_string(), Self::is_untranslated)
+    }
+
+    /// Lists the messages of the given target locale whose source-locale message changed since they were translated,
+    /// i.e. which are marked with a `# fuzzy` comment. Remove the comment once the translation is updated.
+    ///
+    /// Returns a Dictionary mapping each existing FTL file of the locale to a [PackedStringArray] of message identifiers.
+    #[func]
+    pub fn list_outdated(&self, locale: GString) -> Dictionary {
+        self.list_messages(&locale.to_string(), |message| Self::has_comment_line(message, FUZZY_COMMENT))
+    }
+
+    /// Returns the identifiers of messages matching the predicate in each existing FTL file of the locale.
+    fn list_messages(&self, locale: &String, predicate: impl Fn(&ast::Message<String>) -> bool) -> Dictionary {
+        let targets = self.get_matching_files()
+            .into_iter()
+            .flat_map(|(source, pattern)| self.apply_pattern(source, &pattern))
+            .filter(|(_, target_locale)| target_locale.as_ref() == Some(locale))
+            .map(|(target, _)| target)
+            .sorted()
+            .dedup();
+
+        let mut result = Dictionary::new();
+        for target in targets {
+            let Some(ftl) = Self::read_ftl(&target) else {
+                continue;
+            };
+            let ids = ftl.body
+                .iter()
+                .filter_map(|entry| match entry {
+                    ast::Entry::Message(message) if predicate(message) => Some(GString::from(message.id.name.as_str())),
+                    _ => None,
+                })
+                .collect::<PackedStringArray>();
+            result.set(GString::from(target.as_str()), ids);
+        }
+        result
+    }
+
+    /// Parses an existing FTL file, ignoring syntax errors.
+    fn read_ftl(path: &str) -> Option<ast::Resource<String>> {
+        if !FileAccess::file_exists(path) {
+            return None;
+        }
+        match parse(FileAccess::get_file_as_string(path).to_string()) {
+            Ok(ftl) => Some(ftl),
+            Err((ftl, _)) => Some(ftl),
+        }
+    }
+
+    /// Deletes the cache of extracted messages, so that the next run extracts all source files again (see the `generator/use_cache` Project Setting).
+    #[func]
+    pub fn clear_cache() {
+        GeneratorCache::clear();
+    }
+
+    /// Same as [method generate], but only updates the FTL files which messages of the given source files are written to.
+    ///
+    /// Other source files of these FTL files are extracted as well (unless cached, see the `generator/use_cache` Project Setting), so that stale messages are still detected.
+    #[func]
+    pub fn generate_for_sources(&self, sources: PackedStringArray) -> Dictionary {
+        let sources = sources.as_slice().iter().map(GString::to_string).collect::<Vec<_>>();
+        let mut run = self.start_run(false, Some(&sources));
+        while self.step_run(&mut run).is_some() {}
+        run.report
+    }
+
+    fn run(&self, dry_run: bool) -> Dictionary {
+        let mut run = self.start_run(dry_run, None);
+        while self.step_run(&mut run).is_some() {}
+        run.report
+    }
+
+    /// Prepares a generation, which is then advanced one file at a time via [FluentGenerator::step_run].
+    ///
+    /// If `sources` is set, only the FTL files which messages of these source files are written to are generated.
+    pub(crate) fn start_run(&self, dry_run: bool, sources: Option<&[String]>) -> GenerationRun {
+        let mut files = self.get_matching_files();
+        let targets = sources.map(|sources| {
+            files
+                .iter()
+                .filter(|(source, _)| sources.contains(&source.get_string().to_string()))
+                .flat_map(|(source, pattern)| self.apply_pattern(source.clone(), pattern))
+                .map(|(target, _)| target)
+                .collect::<HashSet<_>>()
+        });
+        if let Some(targets) = &targets {
+            // Other source files of the same FTL files are still needed to find stale messages.
+            files.retain(|(source, pattern)| self.apply_pattern(source.clone(), pattern).iter().any(|(target, _)| targets.contains(target)));
+        }
+        GenerationRun {
+            dry_run,
+            targets,
+            file_count: files.len(),
+            files: files.into(),
+            cache: self.use_cache.then(|| GeneratorCache::load(&self.profile_path, Self::get_cache_fingerprint())),
+            tasks: BTreeMap::new(),
+            writes: None,
+            write_count: 0,
+            source_hashes: HashMap::new(),
+            report: Dictionary::new(),
+        }
+    }
+
+    /// Extracts the next source file or, once all are extracted, writes the next FTL file.
+    /// Returns the processed file, or None if the generation is finished.
+    pub(crate) fn step_run(&self, run: &mut GenerationRun) -> Option<GenerationStep> {
+        // Collect source files and batched write operations.
+        if let Some((source, pattern)) = run.files.pop_front() {
+            let source_path = source.get_string();
+            self.extract_source(run, source, &pattern);
+            return Some(GenerationStep {
+                is_writing: false,
+                path: source_path.to_string(),
+                index: run.file_count - run.files.len(),
+                count: run.file_count,
+            });
+        }
 
-                        Some((id, message))
-                    });
-                entry.or_default().extend(&mut messages);
+        if run.writes.is_none() {
+            if let Some(cache) = run.cache.take() {
+                // Source files not part of a partial run may still exist.
+                cache.save(run.targets.is_none());
             }
+            let mut tasks = std::mem::take(&mut run.tasks);
+            for (ftl, task) in tasks.iter_mut().filter(|(_, task)| !task.collisions.is_empty()) {
+                let source_path = task.source_locale_path.as_ref().unwrap_or(ftl);
+                let existing: HashMap<String, String> = Self::read_ftl(source_path)
+                    .map(|source_ftl| source_ftl.body
+                        .into_iter()
+                        .filter_map(|entry| match entry {
+                            ast::Entry::Message(ast::Message { id, value: Some(value), .. }) => Some((id.name, Self::pattern_to_source(&value))),
+                            _ => None,
+                        })
+                        .collect())
+                    .unwrap_or_default();
+                task.resolve_plain_ids(&existing);
+            }
+            run.write_count = tasks.len();
+            // Source-locale files first, so that other locales are compared against their updated messages.
+            run.writes = Some(tasks.into_iter().sorted_by_key(|(_, task)| !task.is_source_locale).collect());
         }
 
         // Do the writes.
-        for (ftl, messages) in generate_tasks {
-            if let Err(_) = Self::create_or_update_ftl(&ftl, messages) {
+        let (ftl, task) = run.writes.as_mut()?.pop_front()?;
+        let is_source_locale = task.is_source_locale;
+        let source_hashes = task.source_locale_path.as_ref().filter(|_| self.track_changes).map(|source_path| {
+            &*run.source_hashes
+                .entry(source_path.clone())
+                .or_insert_with(|| Self::read_ftl(source_path).map(|ftl| Self::get_source_hashes(&ftl)).unwrap_or_default())
+        });
+        match self.create_or_update_ftl(&ftl, task, source_hashes, run.dry_run) {
+            Ok(ftl_report) => {
+                run.report.set(GString::from(ftl.as_str()), ftl_report.to_dictionary());
+                if is_source_locale && self.track_changes {
+                    run.source_hashes.insert(ftl.clone(), ftl_report.source_hashes);
+                }
+            },
+            Err(_) => {
                 godot_error!("FluentGenerator failed to complete for '{ftl}'.");
+            },
+        }
+        Some(GenerationStep {
+            is_writing: true,
+            index: run.write_count - run.writes.as_ref().map_or(0, VecDeque::len),
+            count: run.write_count,
+            path: ftl,
+        })
+    }
+
+    fn extract_source(&self, run: &mut GenerationRun, source: Gd<RegExMatch>, pattern: &String) {
+        let targets = self.apply_pattern(source.clone(), pattern);
+        let source_path = source.get_string();
+        let messages = match run.cache.as_mut() {
+            Some(cache) if self.is_cacheable(&source_path) => cache.get_or_extract(&source_path, || self.get_messages(&source_path)),
+            _ => self.get_messages(&source_path),
+        };
+        for (target, locale) in targets {
+            if run.targets.as_ref().is_some_and(|targets| !targets.contains(&target)) {
+                continue;
+            }
+            let task = run.tasks.entry(target).or_default();
+            task.is_source_locale = locale.as_ref().map_or(true, |locale| *locale == self.source_locale);
+            if !task.is_source_locale {
+                task.source_locale_path = Some(Self::apply_pattern_for_locale(&source, pattern, &self.source_locale));
+            }
+            // Sorted, so that the first text of colliding ids is stable.
+            for (id, message) in messages.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+                if id.is_empty() {
+                    continue;
+                }
+
+                let id = match &message.id_context {
+                    Some(context) => self.derive_id(context),
+                    None => id.clone(),
+                };
+
+                let safe_id = Self::make_safe_identifier(&id);
+                let id = if id != safe_id {
+                    if self.invalid_message_handling == INVALID_MESSAGE_HANDLING_SKIP {
+                        // Skip invalid message.
+                        task.skipped.insert(id);
+                        continue;
+                    } else {
+                        task.renamed.insert(id, safe_id.clone());
+                        safe_id
+                    }
+                } else {
+                    id
+                };
+
+                let id = match &message.id_context {
+                    Some(context) => task.resolve_collision(id, &context.text),
+                    None => id,
+                };
+                task.sources.entry(id.clone()).or_insert_with(|| source_path.to_string());
+                let mut message = message.clone();
+                if self.convert_placeholders {
+                    Self::convert_placeholders(&id, &mut message, &mut task.placeholders);
+                }
+                insert_message(&mut task.messages, id, message);
             }
         }
     }
 
+    /// Replaces printf and [method String.format] placeholders of the message with variables, recording them by message id.
+    fn convert_placeholders(id: &str, message: &mut ExtractedMessage, placeholders: &mut BTreeMap<String, BTreeMap<String, String>>) {
+        let (value, variables) = pattern::convert_placeholders(&message.value);
+        message.value = value;
+        if !variables.is_empty() {
+            placeholders.insert(id.to_owned(), variables);
+        }
+        for (name, attribute) in message.attributes.iter_mut() {
+            let (value, variables) = pattern::convert_placeholders(attribute);
+            *attribute = value;
+            if !variables.is_empty() {
+                placeholders.insert(format!("{id}.{name}"), variables);
+            }
+        }
+    }
+
+    /// Derives the id of a message from its source text, according to the `generator/id_strategy` Project Setting.
+    fn derive_id(&self, context: &IdContext) -> String {
+        if let Some(explicit_id) = &context.explicit_id {
+            return explicit_id.clone();
+        }
+        match self.id_strategy {
+            ID_STRATEGY_SLUG => message_id::slugify(&context.text, self.id_max_length),
+            ID_STRATEGY_NODE_PATH => message_id::slugify(&context.path, 0),
+            ID_STRATEGY_CONTENT_HASH => message_id::content_hash(&context.text),
+            _ => context.text.clone(),
+        }
+    }
+
+    fn instantiate_extractor_script(path: &GString) -> Option<Gd<Object>> {
+        let script = ResourceLoader::singleton().load_ex(path).type_hint("Script").done();
+        let Some(mut script) = script.and_then(|script| script.try_cast::<Script>().ok()) else {
+            godot_error!("Unable to load FluentGenerator extractor script {path}.");
+            return None;
+        };
+        let extractor = script.call("new", &[]).try_to::<Gd<Object>>().ok();
+        if extractor.is_none() {
+            godot_error!("Unable to instantiate FluentGenerator extractor script {path}.");
+        }
+        extractor
+    }
+
     fn get_messages(&self, file: &GString) -> MessageGeneration {
-        self.extractor.extract_messages(file)
+        let mut messages = MessageGeneration::new();
+        for extractor in self.get_extractors(file) {
+            merge_messages(&mut messages, extractor.extract_messages(file));
+        }
+        messages
+    }
+
+    fn get_extractors(&self, file: &GString) -> impl Iterator<Item = &dyn FluentTranslationParser> {
+        let extension = GString::from(PathBuf::from(file.to_string()).extension().unwrap_or_default().to_str().unwrap_or_default());
+        self.extractors
+            .iter()
+            .map(|extractor| extractor.as_ref())
+            .filter(move |extractor| extractor.get_recognized_extensions().contains(&extension))
+    }
+
+    fn is_cacheable(&self, file: &GString) -> bool {
+        self.get_extractors(file).all(|extractor| extractor.is_cacheable())
+    }
+
+    /// Identifies the settings that affect extraction, so that cached messages extracted with other settings are not used.
+    fn get_cache_fingerprint() -> String {
+        let project_settings = ProjectSettings::singleton();
+        let settings = [
+            PROJECT_SETTING_GENERATOR_RESOURCE_INCLUDE_PROPERTIES,
+            PROJECT_SETTING_GENERATOR_RESOURCE_EXCLUDE_PROPERTIES,
+            PROJECT_SETTING_GENERATOR_ATTRIBUTE_PROPERTIES,
+            PROJECT_SETTING_GENERATOR_ID_METADATA_KEY,
+            PROJECT_SETTING_GENERATOR_COMMENT_METADATA_KEY,
+            PROJECT_SETTING_GENERATOR_USE_EDITOR_DESCRIPTION,
+        ];
+        let values = settings.iter().map(|setting| project_settings.get_setting(*setting).stringify()).join(";");
+        format!("{};{}", env!("CARGO_PKG_VERSION"), values)
     }
 
     fn get_matching_files(&self) -> Vec<(Gd<RegExMatch>, String)> {
-        let recognized_extensions = self.extractor.get_recognized_extensions();
-        get_files_recursive("res://".into())
+        let recognized_extensions = self.extractors
+            .iter()
+            .flat_map(|extractor| extractor.get_recognized_extensions())
+            .collect::<HashSet<_>>();
+        get_file_paths_recursive("res://".into())
             .into_iter()
+            .sorted_by_key(|str| str.to_string())
+            .filter(|str| !self.exclude_patterns.iter().any(|regex| regex.search(str).is_some()))
             .filter_map(|str| {
                 // Check all patterns until the first matches (returns Some(RegExMatch)).
                 self.file_patterns.iter().find_map(|(regex, pattern)| {
@@ -127,33 +720,61 @@ impl FluentGenerator {
             .collect()
     }
 
-    fn apply_pattern(&self, source_match: Gd<RegExMatch>, pattern: &String) -> Vec<String> {
+    /// Returns the target paths, together with their locale if the pattern contains `{$locale}`.
+    fn apply_pattern(&self, source_match: Gd<RegExMatch>, pattern: &String) -> Vec<(String, Option<String>)> {
+        let has_locale = pattern.contains("{$locale}");
         self.locales
             .iter()
-            .map(|locale| {
-                let mut pattern = pattern.replace("{$locale}", locale);
-                for group_index in 0..=source_match.get_group_count() {
-                    let group_value = source_match.get_string_ex().name(&group_index.to_variant()).done();
-                    pattern = pattern.replace(&format!("{{${}}}", group_index), &group_value.to_string());
-                }
-                pattern
-            })
-            .unique()
+            .map(|locale| (Self::apply_pattern_for_locale(&source_match, pattern, locale), if has_locale { Some(locale.clone()) } else { None }))
+            .unique_by(|(target, _)| target.clone())
             .collect()
     }
 
-    fn create_or_update_ftl(path: &String, messages: MessageGeneration) -> Result<(), GdErr> {
+    fn apply_pattern_for_locale(source_match: &Gd<RegExMatch>, pattern: &str, locale: &str) -> String {
+        let mut pattern = pattern.replace("{$locale}", locale);
+        for group_index in 0..=source_match.get_group_count() {
+            let group_value = source_match.get_string_ex().name(&group_index.to_variant()).done();
+            pattern = pattern.replace(&format!("{{${}}}", group_index), &group_value.to_string());
+        }
+        pattern
+    }
+
+    /// Writes the messages of the task to the FTL file. For files not in the source locale, `source_hashes` are the hashes of the source-locale messages.
+    fn create_or_update_ftl(&self, path: &String, task: GenerateTask, source_hashes: Option<&HashMap<String, String>>, dry_run: bool) -> Result<GenerationReport, GdErr> {
+        let GenerateTask { messages, renamed, skipped, collisions, placeholders, sources, is_source_locale, .. } = task;
+        for (id, texts) in &collisions {
+            godot_warn!("{} has multiple texts resulting in the message id {}, which were disambiguated: {:?}", path, id, texts);
+        }
+        let mut report = GenerationReport {
+            renamed,
+            skipped,
+            collisions,
+            placeholders,
+            ..Default::default()
+        };
+
         // Load existing or create new FTL file.
-        let fa = create_or_open_file_for_read_write(&path.clone().into());
-        let fa = match fa {
-            Ok(fa) => fa,
-            Err(err) => {
-                godot_error!("Unable to open file {} for writing: {}", path, error_string(err.ord() as i64));
-                return Err(err);
-            }
+        let (fa, text) = if dry_run {
+            let text = if FileAccess::file_exists(path) {
+                FileAccess::get_file_as_string(path)
+            } else {
+                GString::new()
+            };
+            (None, text)
+        } else {
+            let fa = create_or_open_file_for_read_write(&path.clone().into());
+            let fa = match fa {
+                Ok(fa) => fa,
+                Err(err) => {
+                    godot_error!("Unable to open file {} for writing: {}", path, error_string(err.ord() as i64));
+                    return Err(err);
+                }
+            };
+            let text = fa.get_as_text();
+            (Some(fa), text)
         };
 
-        let ftl = parse(fa.get_as_text().to_string());
+        let ftl = parse(text.to_string());
         let mut ftl = match ftl {
             Ok(ftl) => ftl,
             Err((ftl, err)) => {
@@ -162,66 +783,159 @@ impl FluentGenerator {
             },
         };
 
+        // Split off the obsolete group, so that new messages are added before it.
+        let obsolete_entries = match ftl.body.iter().position(Self::is_obsolete_group) {
+            Some(index) => {
+                let mut obsolete_entries = ftl.body.split_off(index);
+                obsolete_entries.remove(0);
+                obsolete_entries
+            },
+            None => vec![],
+        };
+
+        // Move messages that are used again out of the obsolete group.
+        let (mut revived_entries, mut obsolete_entries): (Vec<_>, Vec<_>) = obsolete_entries
+            .into_iter()
+            .partition(|entry| matches!(entry, ast::Entry::Message(msg) if messages.contains_key(&msg.id.name)));
+        ftl.body.append(&mut revived_entries);
+
         // Rewrite FTL.
-        let existing_messages: Vec<&ast::Message<String>> = ftl.body.iter().filter_map(|entry| {
+        let existing_messages: HashSet<String> = ftl.body.iter().filter_map(|entry| {
             if let ast::Entry::Message(msg) = entry {
-                Some(msg)
+                Some(msg.id.name.clone())
             } else {
                 None
             }
         }).collect();
-        let mut new_messages = Vec::new();
+        let is_stale = |entry: &ast::Entry<String>| {
+            matches!(entry, ast::Entry::Message(msg) if !messages.contains_key(&msg.id.name))
+        };
+
+        for entry in ftl.body.iter().chain(obsolete_entries.iter()).filter(|entry| is_stale(*entry)) {
+            if let ast::Entry::Message(msg) = entry {
+                report.stale.insert(msg.id.name.clone());
+            }
+        }
+
+        // Mark or unmark stale messages, and update source references of used messages.
+        for entry in ftl.body.iter_mut().chain(obsolete_entries.iter_mut()) {
+            let stale = is_stale(&*entry);
+            if let ast::Entry::Message(msg) = entry {
+                let annotate = stale && self.stale_message_handling == STALE_MESSAGE_HANDLING_ANNOTATE;
+                Self::set_comment_line(msg, STALE_MESSAGE_COMMENT, annotate);
+                if let Some(message) = messages.get(&msg.id.name) {
+                    Self::set_comment_lines(msg, TRANSLATOR_COMMENT_PREFIX, &message.comments);
+                    if self.source_references {
+                        Self::set_source_references(msg, &message.references);
+                    }
+                    let id = msg.id.name.clone();
+                    if is_source_locale && self.track_changes {
+                        if Self::update_source_text(msg, message) {
+                            report.updated.insert(id);
+                        }
+                    } else {
+                        let added_attributes = Self::add_missing_attributes(msg, message);
+                        if !is_source_locale {
+                            self.seed_target_attributes(msg, &added_attributes);
+                        }
+                        if let Some(source_hash) = source_hashes.and_then(|source_hashes| source_hashes.get(&id)) {
+                            if Self::update_source_hash(msg, source_hash) {
+                                report.outdated.insert(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
 
-        for (identifier, message) in messages {
+        match self.stale_message_handling {
+            STALE_MESSAGE_HANDLING_MOVE_TO_GROUP => {
+                let (mut stale_entries, active_entries): (Vec<_>, Vec<_>) = ftl.body.into_iter().partition(is_stale);
+                for entry in &stale_entries {
+                    if let ast::Entry::Message(msg) = entry {
+                        if !dry_run {
+                            godot_print!("{} moved stale message to obsolete group: {}", path, msg.id.name);
+                        }
+                    }
+                }
+                ftl.body = active_entries;
+                obsolete_entries.append(&mut stale_entries);
+            },
+            STALE_MESSAGE_HANDLING_DELETE => {
+                for entry in ftl.body.iter().chain(obsolete_entries.iter()).filter(|entry| is_stale(*entry)) {
+                    if let ast::Entry::Message(msg) = entry {
+                        if !dry_run {
+                            godot_print!("{} deleted stale message: {}", path, msg.id.name);
+                        }
+                    }
+                }
+                ftl.body.retain(|entry| !is_stale(entry));
+                obsolete_entries.retain(|entry| !is_stale(entry));
+            },
+            _ => {},
+        }
+
+        // Group new messages by source file, sorted by identifier for stable output.
+        let mut new_messages = BTreeMap::<&str, Vec<ast::Entry<String>>>::new();
+        for (identifier, message) in messages.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
             // Check if exists.
-            let has_existing = existing_messages.iter().any(|entry| entry.id.name == identifier);
-            if has_existing {
+            if existing_messages.contains(identifier) {
+                report.unchanged.insert(identifier.clone());
                 continue;
             }
 
             // Add new message.
-            godot_print!("{} added new message: {}", path, message);
-            new_messages.push(ast::Entry::Message(ast::Message {
+            report.added.insert(identifier.clone());
+            if !dry_run {
+                godot_print!("{} added new message: {}", path, identifier);
+            }
+            let mut new_message = ast::Message {
                 id: ast::Identifier {
-                    name: identifier
+                    name: identifier.clone()
                 },
-                value: Some(ast::Pattern {
-                    elements: vec![
-                        ast::PatternElement::TextElement {
-                            value: message,
-                        },
-                    ],
-                }),
+                value: Some(message.value.clone()),
                 attributes: Default::default(),
                 comment: None,
-            }));
-        }
-        // TODO: Add an option to check for deleted messages, and mark them with a comment in the file:
-        /*
-        let mut deleted_messages: Vec<&ast::Message<String>> = existing_messages.clone().into_iter().collect();
-        
-        if existing.is_some() {
-            // Acknowledge existing message.
-            // TODO: could be optimized by creating a wrapper trait so that messages can be put into a HashSet
-            let index = deleted_messages.iter().position(|entry| entry.id.name == message);
-            if let Some(index) = index {
-                deleted_messages.remove(index);
+            };
+            Self::add_missing_attributes(&mut new_message, message);
+            if is_source_locale {
+                if self.track_changes {
+                    let source_hash = Self::hash_message(&new_message);
+                    Self::set_comment_value(&mut new_message, SOURCE_HASH_COMMENT_PREFIX, Some(&source_hash));
+                }
+            } else {
+                if let Some(source_hash) = source_hashes.and_then(|source_hashes| source_hashes.get(identifier)) {
+                    Self::set_comment_value(&mut new_message, SOURCE_HASH_COMMENT_PREFIX, Some(source_hash));
+                }
+                self.seed_target_message(&mut new_message);
             }
+            Self::set_comment_lines(&mut new_message, TRANSLATOR_COMMENT_PREFIX, &message.comments);
+            if self.source_references {
+                Self::set_source_references(&mut new_message, &message.references);
+            }
+            let source = sources.get(identifier).map_or("", String::as_str);
+            new_messages.entry(source).or_default().push(ast::Entry::Message(new_message));
+        }
+        for (source, mut entries) in new_messages {
+            Self::insert_into_group(&mut ftl.body, source, &mut entries);
         }
 
-        // Mark messages that no longer exist with some comment, to have user check for possible deletion.
-        for message in deleted_messages {
-            // TODO: append to existing comment, instead of replacing.
-            message.comment = Some(ast::Comment {
-                content: vec!["TODO: Currently unused, check for deletion?"],
-            });
+        if is_source_locale && self.track_changes {
+            report.source_hashes = Self::get_source_hashes(&ftl);
         }
-        */
 
-        drop(existing_messages);
-        ftl.body.append(&mut new_messages);
+        // Keep the obsolete group at the end of the file.
+        if !obsolete_entries.is_empty() {
+            ftl.body.push(ast::Entry::GroupComment(ast::Comment {
+                content: vec![OBSOLETE_GROUP_COMMENT.to_owned()],
+            }));
+            ftl.body.append(&mut obsolete_entries);
+        }
 
         // Save back to file.
+        let Some(fa) = fa else {
+            return Ok(report);
+        };
         let ftl = serialize(&ftl);
         match Self::truncate_file(fa) {
             Ok(mut fa) => {
@@ -232,10 +946,239 @@ impl FluentGenerator {
                 return Err(err);
             }
         }
-        Ok(())
+        Ok(report)
+    }
+
+    /// Appends entries to the end of the group with the given name, creating the group at the end of the body if it doesn't exist yet.
+    fn insert_into_group(body: &mut Vec<ast::Entry<String>>, group: &str, entries: &mut Vec<ast::Entry<String>>) {
+        if group.is_empty() {
+            body.append(entries);
+            return;
+        }
+
+        let is_group = |entry: &ast::Entry<String>| matches!(entry, ast::Entry::GroupComment(comment) if comment.content.len() == 1 && comment.content[0] == group);
+        match body.iter().position(is_group) {
+            Some(group_index) => {
+                // The group ends at the next group comment.
+                let end_index = body[group_index + 1..]
+                    .iter()
+                    .position(|entry| matches!(entry, ast::Entry::GroupComment(_)))
+                    .map_or(body.len(), |index| group_index + 1 + index);
+                body.splice(end_index..end_index, entries.drain(..));
+            },
+            None => {
+                body.push(ast::Entry::GroupComment(ast::Comment {
+                    content: vec![group.to_owned()],
+                }));
+                body.append(entries);
+            },
+        }
+    }
+
+    fn is_obsolete_group(entry: &ast::Entry<String>) -> bool {
+        matches!(entry, ast::Entry::GroupComment(comment) if comment.content.len() == 1 && comment.content[0] == OBSOLETE_GROUP_COMMENT)
+    }
+
+    /// Adds or removes a line of the message's comment.
+    fn set_comment_line(message: &mut ast::Message<String>, line: &str, enabled: bool) {
+        let has_line = message.comment.as_ref().is_some_and(|comment| comment.content.iter().any(|existing| existing == line));
+        if enabled && !has_line {
+            message.comment.get_or_insert_with(|| ast::Comment { content: vec![] }).content.push(line.to_owned());
+        } else if !enabled && has_line {
+            let comment = message.comment.as_mut().unwrap();
+            comment.content.retain(|existing| existing != line);
+            if comment.content.is_empty() {
+                message.comment = None;
+            }
+        }
+    }
+
+    /// Replaces the source text of a new message in a target locale file, according to the `generator/target_seeding` Project Setting.
+    fn seed_target_message(&self, message: &mut ast::Message<String>) {
+        if matches!(self.target_seeding, TARGET_SEEDING_EMPTY | TARGET_SEEDING_COMMENTED_SOURCE) {
+            if self.target_seeding == TARGET_SEEDING_COMMENTED_SOURCE {
+                if let Some(value) = &message.value {
+                    let comment = message.comment.get_or_insert_with(|| ast::Comment { content: vec![] });
+                    for line in Self::pattern_to_source(value).lines() {
+                        comment.content.push(format!("{SOURCE_TEXT_COMMENT_PREFIX}{line}"));
+                    }
+                }
+            }
+            message.value = Some(Self::empty_pattern());
+        }
+        let attributes = message.attributes.iter().map(|attribute| attribute.id.name.clone()).collect::<Vec<_>>();
+        self.seed_target_attributes(message, &attributes);
+    }
+
+    /// Replaces the source text of the given attributes of a message in a target locale file, like [FluentGenerator::seed_target_message].
+    /// Used for attributes which were added to an existing message.
+    fn seed_target_attributes(&self, message: &mut ast::Message<String>, names: &[String]) {
+        if names.is_empty() {
+            return;
+        }
+        match self.target_seeding {
+            TARGET_SEEDING_EMPTY | TARGET_SEEDING_COMMENTED_SOURCE => {
+                for attribute in message.attributes.iter_mut().filter(|attribute| names.contains(&attribute.id.name)) {
+                    if self.target_seeding == TARGET_SEEDING_COMMENTED_SOURCE {
+                        let comment = message.comment.get_or_insert_with(|| ast::Comment { content: vec![] });
+                        for line in Self::pattern_to_source(&attribute.value).lines() {
+                            comment.content.push(format!("{SOURCE_TEXT_COMMENT_PREFIX}.{} = {line}", attribute.id.name));
+                        }
+                    }
+                    attribute.value = Self::empty_pattern();
+                }
+            },
+            TARGET_SEEDING_TODO_MARKER => {
+                Self::set_comment_line(message, TODO_COMMENT, true);
+            },
+            _ => {},
+        }
+    }
+
+    fn empty_pattern() -> ast::Pattern<String> {
+        ast::Pattern { elements: vec![pattern::string_literal(String::new())] }
+    }
+
+    /// Returns the FTL source of a pattern, as it would be written after `id = `.
+    fn pattern_to_source(value: &ast::Pattern<String>) -> String {
+        value.elements.iter().map(|element| match element {
+            ast::PatternElement::TextElement { value } => value.clone(),
+            placeable => pattern::element_to_source(placeable),
+        }).collect()
+    }
+
+    /// Whether a message of a target locale file still needs to be translated, see [method list_untranslated].
+    fn is_untranslated(message: &ast::Message<String>) -> bool {
+        let has_todo = Self::has_comment_line(message, TODO_COMMENT);
+        let is_empty = |value: &ast::Pattern<String>| {
+            value.elements.iter().all(|element| pattern::string_literal_text(element).is_some_and(|text| text.is_empty()))
+        };
+        has_todo
+            || message.value.as_ref().is_some_and(is_empty)
+            || message.attributes.iter().any(|attribute| is_empty(&attribute.value))
+    }
+
+    /// Returns a hash of the value and attributes of the message, ignoring its comment.
+    fn hash_message(message: &ast::Message<String>) -> String {
+        let message = ast::Message {
+            id: ast::Identifier { name: "x".to_owned() },
+            value: message.value.clone(),
+            attributes: message.attributes.clone(),
+            comment: None,
+        };
+        message_id::text_hash(&serialize(&ast::Resource { body: vec![ast::Entry::Message(message)] }))
+    }
+
+    /// Returns the hash of each message of a source-locale FTL file, which is stored in other locales to detect changes.
+    fn get_source_hashes(ftl: &ast::Resource<String>) -> HashMap<String, String> {
+        ftl.body
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some((message.id.name.clone(), Self::hash_message(message))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Adds missing attributes to a source-locale message and replaces its value and attributes with the extracted text
+    /// if it changed, unless the message was edited since it was generated. Returns whether the message was updated.
+    ///
+    /// The `Source-Hash` comment of source-locale messages contains the hash of the message as last generated.
+    fn update_source_text(message: &mut ast::Message<String>, extracted: &ExtractedMessage) -> bool {
+        let stored_hash = Self::get_comment_value(message, SOURCE_HASH_COMMENT_PREFIX);
+        let is_edited = stored_hash.as_ref().is_some_and(|stored_hash| *stored_hash != Self::hash_message(message));
+        Self::add_missing_attributes(message, extracted);
+        if is_edited {
+            return false;
+        }
+
+        let mut generated = message.clone();
+        generated.value = Some(extracted.value.clone());
+        for attribute in generated.attributes.iter_mut() {
+            if let Some(value) = extracted.attributes.get(&attribute.id.name) {
+                attribute.value = value.clone();
+            }
+        }
+        let generated_hash = Self::hash_message(&generated);
+        let is_changed = generated_hash != Self::hash_message(message);
+        if stored_hash.is_none() && is_changed {
+            // Not tracked yet, e.g. generated before change tracking existed. Only start tracking if it matches the extracted text.
+            return false;
+        }
+        message.value = generated.value;
+        message.attributes = generated.attributes;
+        Self::set_comment_value(message, SOURCE_HASH_COMMENT_PREFIX, Some(&generated_hash));
+        is_changed
+    }
+
+    /// Stores the hash of the source-locale message in a message of another locale, marking it as `fuzzy` if the hash changed.
+    /// Returns whether the message was marked.
+    fn update_source_hash(message: &mut ast::Message<String>, source_hash: &String) -> bool {
+        let stored_hash = Self::get_comment_value(message, SOURCE_HASH_COMMENT_PREFIX);
+        Self::set_comment_value(message, SOURCE_HASH_COMMENT_PREFIX, Some(source_hash));
+        let is_outdated = stored_hash.is_some_and(|stored_hash| stored_hash != *source_hash);
+        if is_outdated {
+            Self::set_comment_line(message, FUZZY_COMMENT, true);
+        }
+        is_outdated
+    }
+
+    /// Whether a comment line is only written for the generator itself, like source references and hashes, rather than for translators.
+    pub(crate) fn is_generated_comment_line(line: &str) -> bool {
+        line.starts_with(SOURCE_REFERENCE_COMMENT_PREFIX) || line.starts_with(SOURCE_HASH_COMMENT_PREFIX)
+    }
+
+    fn has_comment_line(message: &ast::Message<String>, line: &str) -> bool {
+        message.comment.as_ref().is_some_and(|comment| comment.content.iter().any(|existing| existing == line))
+    }
+
+    /// Returns the value of the first comment line starting with `prefix`.
+    fn get_comment_value(message: &ast::Message<String>, prefix: &str) -> Option<String> {
+        message.comment.as_ref()?.content.iter().find_map(|line| line.strip_prefix(prefix).map(str::to_owned))
+    }
+
+    /// Replaces the comment line starting with `prefix`, or removes it if `value` is None.
+    fn set_comment_value(message: &mut ast::Message<String>, prefix: &str, value: Option<&String>) {
+        Self::set_comment_lines(message, prefix, value.map(std::slice::from_ref).unwrap_or_default());
+    }
+
+    /// Replaces all comment lines starting with `prefix` with a line for each of the values.
+    fn set_comment_lines(message: &mut ast::Message<String>, prefix: &str, values: &[String]) {
+        let comment = message.comment.get_or_insert_with(|| ast::Comment { content: vec![] });
+        comment.content.retain(|line| !line.starts_with(prefix));
+        comment.content.extend(values.iter().map(|value| format!("{prefix}{value}")));
+        if comment.content.is_empty() {
+            message.comment = None;
+        }
+    }
+
+    /// Adds attributes that were extracted but do not exist in the message yet. Existing attributes are not updated.
+    /// Returns the names of the added attributes.
+    fn add_missing_attributes(message: &mut ast::Message<String>, extracted: &ExtractedMessage) -> Vec<String> {
+        let mut added = vec![];
+        for (name, value) in &extracted.attributes {
+            if message.attributes.iter().any(|attribute| attribute.id.name == *name) {
+                continue;
+            }
+            message.attributes.push(ast::Attribute {
+                id: ast::Identifier { name: name.clone() },
+                value: value.clone(),
+            });
+            added.push(name.clone());
+        }
+        added
+    }
+
+    /// Replaces the source reference lines of the message's comment.
+    fn set_source_references(message: &mut ast::Message<String>, references: &[String]) {
+        let mut references = references.to_vec();
+        references.sort();
+        references.dedup();
+
+        Self::set_comment_lines(message, SOURCE_REFERENCE_COMMENT_PREFIX, &references);
     }
 
-    fn make_safe_identifier(name: &String) -> String {
+    pub(crate) fn make_safe_identifier(name: &String) -> String {
         // Identifiers are [a-zA-Z][a-zA-Z0-9_-]*
         if name.is_empty() {
             panic!("Identifier name can't be empty.");
@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use fluent_syntax::{ast, parser::parse, serializer::serialize};
+use godot::classes::file_access::ModeFlags;
+use godot::classes::{DirAccess, FileAccess, ResourceLoader};
+use godot::global::error_string;
+use godot::prelude::*;
+use itertools::Itertools;
+
+use crate::utils::create_file_for_write;
+
+use super::{message_id, ExtractedMessage, IdContext, MessageGeneration};
+
+/// Folder the extracted messages of previous runs are stored in, one file per generator profile.
+const CACHE_DIR: &str = "res://.godot/fluent";
+const CACHE_FILE_PREFIX: &str = "generator_cache";
+/// Increase when the format of the cache or the output of the built-in extractors changes.
+const CACHE_VERSION: i64 = 3;
+
+/// Messages extracted by previous runs of [FluentGenerator](super::FluentGenerator), so that only changed source files are extracted again.
+///
+/// A source file is considered changed if its content or the content of any of its (recursive) dependencies changed,
+/// e.g. a scene instanced by it. The whole cache is discarded if the settings it was created with changed.
+pub(crate) struct GeneratorCache {
+    path: String,
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+    /// Source files extracted or looked up during this run. All others are removed when saving a full run.
+    used: HashSet<String>,
+}
+
+struct CacheEntry {
+    hash: String,
+    messages: MessageGeneration,
+}
+
+impl GeneratorCache {
+    /// Loads the cache of the generator profile saved at `profile_path` (empty for the Project Settings),
+    /// or creates an empty one if it does not exist or was created with a different `fingerprint`.
+    pub fn load(profile_path: &str, fingerprint: String) -> Self {
+        let path = match profile_path {
+            "" => format!("{CACHE_DIR}/{CACHE_FILE_PREFIX}.bin"),
+            profile_path => format!("{CACHE_DIR}/{CACHE_FILE_PREFIX}_{}.bin", message_id::text_hash(profile_path)),
+        };
+        let mut cache = Self {
+            path,
+            fingerprint,
+            entries: HashMap::new(),
+            used: HashSet::new(),
+        };
+        if !FileAccess::file_exists(cache.path.as_str()) {
+            return cache;
+        }
+        let Some(mut fa) = FileAccess::open(cache.path.as_str(), ModeFlags::READ) else {
+            return cache;
+        };
+        let Ok(data) = fa.get_var().try_to::<Dictionary>() else {
+            return cache;
+        };
+
+        let is_compatible = data.get("version").is_some_and(|version| version.try_to::<i64>() == Ok(CACHE_VERSION))
+            && data.get("fingerprint").is_some_and(|fingerprint| fingerprint.stringify().to_string() == cache.fingerprint);
+        if !is_compatible {
+            return cache;
+        }
+
+        let sources = data.get("sources").and_then(|sources| sources.try_to::<Dictionary>().ok()).unwrap_or_default();
+        for (path, entry) in sources.iter_shared() {
+            let Ok(entry) = entry.try_to::<Dictionary>() else {
+                continue;
+            };
+            let hash = entry.get("hash").map(|hash| hash.stringify().to_string()).unwrap_or_default();
+            let messages = entry.get("messages").and_then(|messages| messages.try_to::<Dictionary>().ok()).unwrap_or_default();
+            let messages = messages
+                .iter_shared()
+                .filter_map(|(id, message)| Some((id.stringify().to_string(), Self::message_from_variant(&message)?)))
+                .collect();
+            cache.entries.insert(path.stringify().to_string(), CacheEntry { hash, messages });
+        }
+        cache
+    }
+
+    /// Deletes the cache files of all profiles, so that the next run extracts all source files again.
+    pub fn clear() {
+        if !DirAccess::dir_exists_absolute(CACHE_DIR) {
+            return;
+        }
+        for file in DirAccess::get_files_at(CACHE_DIR).as_slice() {
+            if file.to_string().starts_with(CACHE_FILE_PREFIX) {
+                DirAccess::remove_absolute(format!("{CACHE_DIR}/{file}").as_str());
+            }
+        }
+    }
+
+    /// Returns the cached messages of the source file if it did not change, otherwise extracts and caches them.
+    pub fn get_or_extract(&mut self, path: &GString, extract: impl FnOnce() -> MessageGeneration) -> MessageGeneration {
+        let key = path.to_string();
+        let hash = Self::hash_source(path);
+        self.used.insert(key.clone());
+        if let Some(entry) = self.entries.get(&key) {
+            if entry.hash == hash {
+                return entry.messages.clone();
+            }
+        }
+
+        let messages = extract();
+        self.entries.insert(key, CacheEntry { hash, messages: messages.clone() });
+        messages
+    }
+
+    /// Writes the cache. If `prune` is set, only source files used during this run are kept.
+    pub fn save(&self, prune: bool) {
+        let mut sources = Dictionary::new();
+        for (path, entry) in self.entries.iter().filter(|(path, _)| !prune || self.used.contains(*path)) {
+            let mut messages = Dictionary::new();
+            for (id, message) in &entry.messages {
+                messages.set(GString::from(id.as_str()), Self::message_to_variant(message));
+            }
+            let mut cached = Dictionary::new();
+            cached.set("hash", GString::from(entry.hash.as_str()));
+            cached.set("messages", messages);
+            sources.set(GString::from(path.as_str()), cached);
+        }
+
+        let mut data = Dictionary::new();
+        data.set("version", CACHE_VERSION);
+        data.set("fingerprint", GString::from(self.fingerprint.as_str()));
+        data.set("sources", sources);
+        match create_file_for_write(&self.path.as_str().into()) {
+            Ok(mut fa) => {
+                fa.store_var(&data.to_variant());
+            },
+            Err(err) => godot_warn!("Unable to write FluentGenerator cache {}: {}", self.path, error_string(err.ord() as i64)),
+        }
+    }
+
+    /// Combines the MD5 hashes of the file and all of its dependencies.
+    fn hash_source(path: &GString) -> String {
+        let mut hashes = BTreeMap::new();
+        let mut pending = vec![path.to_string()];
+        while let Some(path) = pending.pop() {
+            if hashes.contains_key(&path) {
+                continue;
+            }
+            hashes.insert(path.clone(), FileAccess::get_md5(path.as_str()).to_string());
+            for dependency in ResourceLoader::singleton().get_dependencies(path.as_str()).as_slice() {
+                // Dependencies are formatted like `uid://abc::Type::res://path`.
+                let dependency = dependency.to_string();
+                let dependency_path = dependency.rsplit("::").next().unwrap_or_default();
+                if !dependency_path.is_empty() {
+                    pending.push(dependency_path.to_owned());
+                }
+            }
+        }
+        hashes.into_iter().map(|(path, hash)| format!("{path}={hash}")).join(";")
+    }
+
+    fn message_to_variant(message: &ExtractedMessage) -> Dictionary {
+        // The patterns are stored as FTL, which they are written as anyway.
+        let ftl = ast::Resource {
+            body: vec![ast::Entry::Message(ast::Message {
+                id: ast::Identifier { name: "x".to_owned() },
+                value: Some(message.value.clone()),
+                attributes: message.attributes
+                    .iter()
+                    .map(|(name, value)| ast::Attribute {
+                        id: ast::Identifier { name: name.clone() },
+                        value: value.clone(),
+                    })
+                    .collect(),
+                comment: None,
+            })],
+        };
+
+        let mut dictionary = Dictionary::new();
+        dictionary.set("ftl", GString::from(serialize(&ftl).as_str()));
+        dictionary.set("references", message.references.iter().map(|reference| GString::from(reference.as_str())).collect::<PackedStringArray>());
+        dictionary.set("comments", message.comments.iter().map(|comment| GString::from(comment.as_str())).collect::<PackedStringArray>());
+        if let Some(context) = &message.id_context {
+            let mut id_context = Dictionary::new();
+            id_context.set("text", GString::from(context.text.as_str()));
+            id_context.set("path", GString::from(context.path.as_str()));
+            if let Some(explicit_id) = &context.explicit_id {
+                id_context.set("explicit_id", GString::from(explicit_id.as_str()));
+            }
+            dictionary.set("id_context", id_context);
+        }
+        dictionary
+    }
+
+    fn message_from_variant(value: &Variant) -> Option<ExtractedMessage> {
+        let dictionary = value.try_to::<Dictionary>().ok()?;
+        let ftl = parse(dictionary.get("ftl")?.stringify().to_string()).ok()?;
+        let Some(ast::Entry::Message(message)) = ftl.body.into_iter().next() else {
+            return None;
+        };
+
+        let references = dictionary.get("references")?.try_to::<PackedStringArray>().ok()?;
+        let comments = dictionary.get("comments")?.try_to::<PackedStringArray>().ok()?;
+        let id_context = match dictionary.get("id_context") {
+            Some(id_context) => {
+                let id_context = id_context.try_to::<Dictionary>().ok()?;
+                Some(IdContext {
+                    text: id_context.get("text")?.stringify().to_string(),
+                    path: id_context.get("path")?.stringify().to_string(),
+                    explicit_id: id_context.get("explicit_id").map(|explicit_id| explicit_id.stringify().to_string()),
+                })
+            },
+            None => None,
+        };
+        Some(ExtractedMessage {
+            value: message.value?,
+            references: references.as_slice().iter().map(|reference| reference.to_string()).collect(),
+            attributes: message.attributes.into_iter().map(|attribute| (attribute.id.name, attribute.value)).collect(),
+            comments: comments.as_slice().iter().map(|comment| comment.to_string()).collect(),
+            id_context,
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use godot::classes::{IResource, ProjectSettings};
+use godot::prelude::*;
+
+use super::project_settings::*;
+
+/// Configuration of a [FluentGenerator], see [method FluentGenerator.create_from].
+///
+/// Allows keeping several generator profiles (e.g. for UI strings and dialogue) with different file patterns and outputs.
+/// A new config has the same defaults as the generator Project Settings; use [method from_project_settings] to copy their current values instead.
+/// Which properties are extracted from scenes and resources (e.g. `generator/resource_include_properties`) is still configured via Project Settings.
+#[derive(GodotClass)]
+#[class(tool, base=Resource)]
+pub struct FluentGeneratorConfig {
+    /// Locales to generate FTL files for, replacing `{$locale}` in [member file_patterns].
+    #[export]
+    pub locales: PackedStringArray,
+    /// Maps regular expressions matching source files to the path of the FTL file to generate, see the `generator/file_patterns` Project Setting.
+    #[export]
+    pub file_patterns: Dictionary,
+    /// Source files whose path matches any of these regular expressions are skipped.
+    #[export]
+    pub exclude_patterns: PackedStringArray,
+    /// Scripts instantiated as additional extractors, see [method FluentGenerator.add_extractor].
+    #[export]
+    pub extractor_scripts: PackedStringArray,
+    /// How messages with an id that is not a valid Fluent identifier are handled.
+    #[export(enum = (SkipMessage = 0, ConvertToValid = 1))]
+    pub invalid_message_handling: i32,
+    /// How messages are handled that no longer exist in any source file.
+    #[export(enum = (Leave = 0, Annotate = 1, MoveToObsoleteGroup = 2, Delete = 3))]
+    pub stale_message_handling: i32,
+    /// Whether messages get `# Source: ...` comments with the locations they were found at.
+    #[export]
+    pub source_references: bool,
+    /// How message ids are derived from texts found in scenes and resources.
+    #[export(enum = (SourceText = 0, Slug = 1, NodePath = 2, ContentHash = 3))]
+    pub id_strategy: i32,
+    /// Maximum length of ids derived by the `Slug` strategy, `0` meaning unlimited.
+    #[export(range = (0.0, 256.0, or_greater))]
+    pub id_max_length: i64,
+    /// Locale the extracted texts are written in. If empty, the fallback locale is used.
+    #[export]
+    pub source_locale: GString,
+    /// How new messages are written to FTL files of locales other than [member source_locale].
+    #[export(enum = (SourceText = 0, Empty = 1, CommentedSourceText = 2, TodoMarker = 3))]
+    pub target_seeding: i32,
+    /// Whether printf and `String.format` placeholders in extracted texts are converted into Fluent variables.
+    #[export]
+    pub convert_placeholders: bool,
+    /// Whether extracted messages are cached, so that only changed source files are extracted again.
+    #[export]
+    pub use_cache: bool,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for FluentGeneratorConfig {
+    fn init(base: Base<Resource>) -> Self {
+        // Fixed defaults (same as the Project Settings), since values equal to the default are not saved in the resource.
+        Self {
+            locales: PackedStringArray::new(),
+            file_patterns: Dictionary::new(),
+            exclude_patterns: PackedStringArray::from(&["^res://addons/"].map(GString::from)),
+            extractor_scripts: PackedStringArray::new(),
+            invalid_message_handling: INVALID_MESSAGE_HANDLING_SKIP,
+            stale_message_handling: STALE_MESSAGE_HANDLING_LEAVE,
+            source_references: true,
+            id_strategy: ID_STRATEGY_SOURCE_TEXT,
+            id_max_length: 40,
+            source_locale: GString::new(),
+            target_seeding: TARGET_SEEDING_SOURCE_TEXT,
+            convert_placeholders: false,
+            use_cache: true,
+            base,
+        }
+    }
+}
+
+#[godot_api]
+impl FluentGeneratorConfig {
+    /// Creates a config with the current values of the generator Project Settings, e.g. as a starting point for a new profile.
+    #[func]
+    pub fn from_project_settings() -> Gd<Self> {
+        let project_settings = ProjectSettings::singleton();
+        let setting = |name: &str| project_settings.get_setting(name);
+        let mut config = Self::new_gd();
+        {
+            let mut config = config.bind_mut();
+            config.locales = PackedStringArray::from_variant(&setting(PROJECT_SETTING_GENERATOR_LOCALES));
+            config.file_patterns = Dictionary::from_variant(&setting(PROJECT_SETTING_GENERATOR_PATTERNS)).duplicate_deep();
+            config.exclude_patterns = PackedStringArray::from_variant(&setting(PROJECT_SETTING_GENERATOR_EXCLUDE_PATTERNS));
+            config.extractor_scripts = PackedStringArray::from_variant(&setting(PROJECT_SETTING_GENERATOR_EXTRACTOR_SCRIPTS));
+            config.invalid_message_handling = i32::from_variant(&setting(PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING));
+            config.stale_message_handling = i32::from_variant(&setting(PROJECT_SETTING_GENERATOR_STALE_MESSAGE_HANDLING));
+            config.source_references = bool::from_variant(&setting(PROJECT_SETTING_GENERATOR_SOURCE_REFERENCES));
+            config.id_strategy = i32::from_variant(&setting(PROJECT_SETTING_GENERATOR_ID_STRATEGY));
+            config.id_max_length = i64::from_variant(&setting(PROJECT_SETTING_GENERATOR_ID_MAX_LENGTH));
+            config.source_locale = GString::from_variant(&setting(PROJECT_SETTING_GENERATOR_SOURCE_LOCALE));
+            config.target_seeding = i32::from_variant(&setting(PROJECT_SETTING_GENERATOR_TARGET_SEEDING));
+            config.convert_placeholders = bool::from_variant(&setting(PROJECT_SETTING_GENERATOR_CONVERT_PLACEHOLDERS));
+            config.use_cache = bool::from_variant(&setting(PROJECT_SETTING_GENERATOR_USE_CACHE));
+        }
+        config
+    }
+}
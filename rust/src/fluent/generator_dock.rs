@@ -0,0 +1,229 @@
+use godot::classes::control::SizeFlags;
+use godot::classes::text_server::AutowrapMode;
+use godot::classes::{Button, EditorInterface, HBoxContainer, IVBoxContainer, Label, OptionButton, ProgressBar, Tree, TreeItem, VBoxContainer};
+use godot::prelude::*;
+
+use super::{FluentGenerator, GenerationRun};
+
+/// Editor dock to run the [FluentGenerator] and show its progress and report. For internal use only.
+#[derive(GodotClass)]
+#[class(tool, init, base=VBoxContainer)]
+pub struct FluentGeneratorDock {
+    generator: Option<Gd<FluentGenerator>>,
+    run: Option<GenerationRun>,
+    dry_run: bool,
+    buttons: Vec<Gd<Button>>,
+    profile_button: Option<Gd<OptionButton>>,
+    progress_bar: Option<Gd<ProgressBar>>,
+    status_label: Option<Gd<Label>>,
+    report_tree: Option<Gd<Tree>>,
+    base: Base<VBoxContainer>,
+}
+
+#[godot_api]
+impl IVBoxContainer for FluentGeneratorDock {
+    fn ready(&mut self) {
+        self.base_mut().set_name("Fluent");
+
+        let mut profile_button = OptionButton::new_alloc();
+        profile_button.set_tooltip_text("Generator configuration, see the generator/profiles Project Setting.");
+        profile_button.connect("pressed", &self.base().callable("refresh_profiles"));
+        self.base_mut().add_child(&profile_button);
+        self.profile_button = Some(profile_button);
+        self.refresh_profiles();
+
+        let mut buttons = HBoxContainer::new_alloc();
+        for (text, method) in [("Generate", "generate"), ("Dry Run", "generate_dry_run")] {
+            let mut button = Button::new_alloc();
+            button.set_text(text);
+            button.set_h_size_flags(SizeFlags::EXPAND_FILL);
+            button.connect("pressed", &self.base().callable(method));
+            buttons.add_child(&button);
+            self.buttons.push(button);
+        }
+        self.base_mut().add_child(&buttons);
+
+        let mut progress_bar = ProgressBar::new_alloc();
+        progress_bar.set_visible(false);
+        self.base_mut().add_child(&progress_bar);
+        self.progress_bar = Some(progress_bar);
+
+        let mut status_label = Label::new_alloc();
+        status_label.set_text("Extracts messages into FTL files according to the generator Project Settings.");
+        status_label.set_autowrap_mode(AutowrapMode::WORD_SMART);
+        self.base_mut().add_child(&status_label);
+        self.status_label = Some(status_label);
+
+        let mut report_tree = Tree::new_alloc();
+        report_tree.set_hide_root(true);
+        report_tree.set_v_size_flags(SizeFlags::EXPAND_FILL);
+        self.base_mut().add_child(&report_tree);
+        self.report_tree = Some(report_tree);
+
+        self.base_mut().set_process(false);
+    }
+
+    fn process(&mut self, _delta: f64) {
+        let (Some(generator), Some(run)) = (&self.generator, &mut self.run) else {
+            return;
+        };
+
+        // One file per frame, so that the progress is drawn.
+        let step = generator.bind().step_run(run);
+        match step {
+            Some(step) => {
+                let action = if step.is_writing { "Writing" } else { "Extracting" };
+                self.set_status(&format!("{action} ({}/{}): {}", step.index, step.count, step.path));
+                if let Some(progress_bar) = self.progress_bar.as_mut() {
+                    // Extracting is the first half, writing the second.
+                    let offset = if step.is_writing { 50.0 } else { 0.0 };
+                    progress_bar.set_value(offset + 50.0 * step.index as f64 / step.count.max(1) as f64);
+                }
+            },
+            None => self.finish(),
+        }
+    }
+}
+
+#[godot_api]
+impl FluentGeneratorDock {
+    /// Starts generating FTL files, see [method FluentGenerator.generate].
+    #[func]
+    pub fn generate(&mut self) {
+        self.start(false);
+    }
+
+    /// Starts computing the report without writing files, see [method FluentGenerator.generate_dry_run].
+    #[func]
+    pub fn generate_dry_run(&mut self) {
+        self.start(true);
+    }
+
+    /// Lists the Project Settings and all profiles, keeping the selected one.
+    #[func]
+    fn refresh_profiles(&mut self) {
+        let Some(profile_button) = self.profile_button.as_mut() else {
+            return;
+        };
+        let selected = profile_button.get_selected_metadata().try_to::<GString>().unwrap_or_default();
+        profile_button.clear();
+        profile_button.add_item("Project Settings");
+        profile_button.set_item_metadata(0, &GString::new().to_variant());
+        for (index, path) in FluentGenerator::get_profiles().as_slice().iter().enumerate() {
+            profile_button.add_item(path);
+            profile_button.set_item_metadata(index as i32 + 1, &path.to_variant());
+            if *path == selected {
+                profile_button.select(index as i32 + 1);
+            }
+        }
+    }
+
+    fn start(&mut self, dry_run: bool) {
+        if self.run.is_some() {
+            return;
+        }
+
+        // Created for each run, so that changed Project Settings are used.
+        let profile = self.profile_button
+            .as_ref()
+            .map(|button| button.get_selected_metadata().try_to::<GString>().unwrap_or_default())
+            .filter(|path| !path.is_empty());
+        let Some(generator) = FluentGenerator::create_for_profile(profile.as_ref()) else {
+            self.set_status("Unable to load the selected profile.");
+            return;
+        };
+        self.run = Some(generator.bind().start_run(dry_run, None));
+        self.dry_run = dry_run;
+        self.generator = Some(generator);
+
+        for button in self.buttons.iter_mut() {
+            button.set_disabled(true);
+        }
+        if let Some(progress_bar) = self.progress_bar.as_mut() {
+            progress_bar.set_value(0.0);
+            progress_bar.set_visible(true);
+        }
+        if let Some(report_tree) = self.report_tree.as_mut() {
+            report_tree.clear();
+        }
+        self.set_status("Collecting files...");
+        self.base_mut().set_process(true);
+    }
+
+    fn finish(&mut self) {
+        self.base_mut().set_process(false);
+        self.generator = None;
+        let Some(run) = self.run.take() else {
+            return;
+        };
+
+        for button in self.buttons.iter_mut() {
+            button.set_disabled(false);
+        }
+        if let Some(progress_bar) = self.progress_bar.as_mut() {
+            progress_bar.set_visible(false);
+        }
+
+        let added = run.report
+            .values_array()
+            .iter_shared()
+            .map(|ftl_report| Dictionary::from_variant(&ftl_report).get("added").map_or(0, |added| PackedStringArray::from_variant(&added).len()))
+            .sum::<usize>();
+        let verb = if self.dry_run { "would be added" } else { "added" };
+        self.set_status(&format!("Done: {} FTL files, {added} messages {verb}.", run.report.len()));
+        self.show_report(&run.report);
+
+        if !self.dry_run {
+            // Show new FTL files in the FileSystem dock.
+            if let Some(mut file_system) = EditorInterface::singleton().get_resource_filesystem() {
+                file_system.scan();
+            }
+        }
+    }
+
+    fn show_report(&mut self, report: &Dictionary) {
+        let Some(tree) = self.report_tree.as_mut() else {
+            return;
+        };
+        let root = tree.create_item().unwrap();
+        for (ftl, ftl_report) in report.iter_shared() {
+            let mut ftl_item = tree.create_item_ex().parent(&root).done().unwrap();
+            ftl_item.set_text(0, &ftl.stringify());
+            for (category, ids) in Dictionary::from_variant(&ftl_report).iter_shared() {
+                Self::add_report_category(tree, &ftl_item, &category.stringify(), &ids);
+            }
+        }
+    }
+
+    /// Adds a collapsed item for a category of the report, e.g. `added (3)`, with an item for each of its entries.
+    fn add_report_category(tree: &mut Gd<Tree>, parent: &Gd<TreeItem>, category: &GString, entries: &Variant) {
+        let entries = match entries.try_to::<Dictionary>() {
+            Ok(dictionary) => dictionary
+                .iter_shared()
+                .map(|(key, value)| format!("{key}: {value}"))
+                .collect::<Vec<_>>(),
+            Err(_) => PackedStringArray::from_variant(entries)
+                .as_slice()
+                .iter()
+                .map(GString::to_string)
+                .collect(),
+        };
+        if entries.is_empty() {
+            return;
+        }
+
+        let mut category_item = tree.create_item_ex().parent(parent).done().unwrap();
+        category_item.set_text(0, format!("{category} ({})", entries.len()).as_str());
+        category_item.set_collapsed(true);
+        for entry in entries {
+            let mut entry_item = tree.create_item_ex().parent(&category_item).done().unwrap();
+            entry_item.set_text(0, entry.as_str());
+        }
+    }
+
+    fn set_status(&mut self, status: &str) {
+        if let Some(status_label) = self.status_label.as_mut() {
+            status_label.set_text(status);
+        }
+    }
+}
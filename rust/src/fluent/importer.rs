@@ -1,10 +1,12 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
 
 use godot::prelude::*;
 use godot::classes::{FileAccess, IResourceFormatLoader, ProjectSettings, RegEx, ResourceFormatLoader};
 use godot::global::Error as GdErr;
 
-use super::{locale::{compute_locale, compute_message_pattern}, project_settings::*, TranslationFluent};
+use super::{locale::{compute_locale, compute_message_pattern, compute_terms_files, get_terms_file_regex, is_terms_file}, project_settings::*, TranslationFluent};
 
 /// Loads Fluent Translation List (FTL) files.
 /// 
@@ -39,7 +41,7 @@ impl IResourceFormatLoader for ResourceFormatLoaderFluent {
         }.into()
     }
 
-    fn load(&self, path: GString, _original_path: GString, _use_sub_threads: bool, _cache_mode: i32) -> Variant {
+    fn load(&self, path: GString, _original_path: GString, _use_sub_threads: bool, cache_mode: i32) -> Variant {
         let path_buf: String = path.clone().into();
         let path_buf = PathBuf::from(path_buf);
         let locale = compute_locale(&path_buf);
@@ -53,8 +55,32 @@ impl IResourceFormatLoader for ResourceFormatLoaderFluent {
             return err.ord().to_variant();
         }
 
+        let locale = locale.unwrap();
+        let terms_cache_mode = TermsCacheMode::from_cache_mode(cache_mode);
+
+        // Collect terms of all shared terms files with the same locale.
+        let terms_texts = if get_terms_file_regex().is_some_and(|terms_regex| is_terms_file(&path_buf, &terms_regex)) {
+            vec![]
+        } else {
+            Self::get_terms_files(&locale, terms_cache_mode)
+                .into_iter()
+                .filter_map(|terms_path| {
+                    let terms_text = Self::read_terms_file(&terms_path, terms_cache_mode);
+                    if terms_text.is_none() {
+                        godot_warn!("Unable to read shared terms file {}, ignoring.", terms_path);
+                    }
+                    terms_text.map(|terms_text| (terms_path, terms_text))
+                })
+                .collect()
+        };
+
+        let res = match TranslationFluent::parse_resource(text.to_string()) {
+            Ok(res) => res,
+            Err(err) => return err.ord().to_variant(),
+        };
+
         let mut translation = TranslationFluent::new_gd();
-        translation.bind_mut().base_mut().set_locale(&locale.unwrap());
+        translation.bind_mut().base_mut().set_locale(&locale);
 
         let pattern_match = compute_message_pattern(&path_buf);
         if let Some(pattern_match) = pattern_match {
@@ -76,11 +102,112 @@ impl IResourceFormatLoader for ResourceFormatLoaderFluent {
             }
         }
 
-        let err = translation.bind_mut().append_from_text(text.to_string());
+        let err = translation.bind_mut().append_resource(res);
         if err != GdErr::OK {
             return err.ord().to_variant();
         }
 
+        // Merge shared terms after the file's own entries, so that local terms take precedence.
+        for (terms_path, terms_text) in terms_texts {
+            let err = match TranslationFluent::parse_terms_resource(terms_text) {
+                Ok(terms_res) => translation.bind_mut().append_terms_resource(terms_res),
+                Err(err) => err,
+            };
+            if err != GdErr::OK {
+                godot_warn!("Unable to add shared terms of {} to {}, ignoring.", terms_path, path);
+            }
+        }
+
         translation.to_variant()
     }
 }
+
+/// How the loader's shared terms cache is used, derived from [CacheMode].
+/// Shared terms files are treated like external dependencies of the loaded file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TermsCacheMode {
+    Reuse,
+    Replace,
+    Ignore,
+}
+
+impl TermsCacheMode {
+    // Since CACHE_MODE_*_DEEP.
+    #[cfg(since_api = "4.3")]
+    fn from_cache_mode(cache_mode: i32) -> Self {
+        use godot::classes::resource_loader::CacheMode;
+
+        match CacheMode::from_ord(cache_mode) {
+            CacheMode::IGNORE_DEEP => Self::Ignore,
+            CacheMode::REPLACE_DEEP => Self::Replace,
+            // Dependencies are reused for all other modes.
+            _ => Self::Reuse,
+        }
+    }
+
+    // Before CACHE_MODE_*_DEEP.
+    #[cfg(before_api = "4.3")]
+    fn from_cache_mode(_cache_mode: i32) -> Self {
+        Self::Reuse
+    }
+}
+
+#[derive(Default)]
+struct TermsCache {
+    /// Locale -> paths of its shared terms files. Cleared when the file system changes in the editor.
+    files_by_locale: HashMap<String, Vec<String>>,
+    /// Path -> (modified time, file contents).
+    texts: HashMap<String, (u64, String)>,
+}
+
+fn terms_cache() -> &'static Mutex<TermsCache> {
+    static TERMS_CACHE: OnceLock<Mutex<TermsCache>> = OnceLock::new();
+    TERMS_CACHE.get_or_init(Default::default)
+}
+
+impl ResourceFormatLoaderFluent {
+    /// Forgets which shared terms files exist, e.g. after files were added, renamed or deleted.
+    pub(crate) fn clear_terms_files_cache() {
+        terms_cache().lock().unwrap().files_by_locale.clear();
+    }
+
+    fn get_terms_files(locale: &String, cache_mode: TermsCacheMode) -> Vec<String> {
+        if cache_mode == TermsCacheMode::Reuse {
+            if let Some(files) = terms_cache().lock().unwrap().files_by_locale.get(locale) {
+                return files.clone();
+            }
+        }
+
+        let files = compute_terms_files(locale)
+            .into_iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>();
+        if cache_mode != TermsCacheMode::Ignore {
+            terms_cache().lock().unwrap().files_by_locale.insert(locale.clone(), files.clone());
+        }
+        files
+    }
+
+    fn read_terms_file(path: &String, cache_mode: TermsCacheMode) -> Option<String> {
+        let gpath = GString::from(path.as_str());
+        let modified_time = FileAccess::get_modified_time(&gpath);
+        if cache_mode == TermsCacheMode::Reuse {
+            if let Some((cached_time, text)) = terms_cache().lock().unwrap().texts.get(path) {
+                if *cached_time == modified_time {
+                    return Some(text.clone());
+                }
+            }
+        }
+
+        let text = FileAccess::get_file_as_string(&gpath);
+        if FileAccess::get_open_error() != GdErr::OK {
+            return None;
+        }
+
+        let text = text.to_string();
+        if cache_mode != TermsCacheMode::Ignore {
+            terms_cache().lock().unwrap().texts.insert(path.clone(), (modified_time, text.clone()));
+        }
+        Some(text)
+    }
+}
@@ -4,7 +4,7 @@ use godot::classes::{ProjectSettings, RegEx, RegExMatch};
 use godot::prelude::*;
 use unic_langid::LanguageIdentifier;
 
-use crate::utils::get_single_regex_match;
+use crate::utils::{get_file_paths_recursive, get_single_regex_match};
 
 use super::project_settings::*;
 
@@ -91,6 +91,40 @@ pub fn compute_locale(path: &PathBuf) -> Option<String> {
     None
 }
 
+/// Compiles the shared terms file regex, or returns None if none is configured.
+pub fn get_terms_file_regex() -> Option<Gd<RegEx>> {
+    let project_settings = ProjectSettings::singleton();
+    let terms_regex = project_settings.get_setting(PROJECT_SETTING_LOADER_TERMS_FILE_REGEX).stringify();
+    if terms_regex.is_empty() {
+        return None;
+    }
+    RegEx::create_from_string(&terms_regex)
+}
+
+/// Whether the file name matches the shared terms file regex (see [get_terms_file_regex]).
+pub fn is_terms_file(path: &PathBuf, terms_regex: &Gd<RegEx>) -> bool {
+    let Some(file_name) = path.file_name() else {
+        return false;
+    };
+    let file_name = GString::from(file_name.to_owned().into_string().unwrap());
+    terms_regex.search(&file_name).is_some()
+}
+
+/// Finds all shared terms files in the project which belong to the given locale.
+pub fn compute_terms_files(locale: &str) -> Vec<GString> {
+    let Some(terms_regex) = get_terms_file_regex() else {
+        return vec![];
+    };
+
+    get_file_paths_recursive("res://".into())
+        .into_iter()
+        .filter(|path| {
+            let path_buf = PathBuf::from(path.to_string());
+            is_terms_file(&path_buf, &terms_regex) && compute_locale(&path_buf).is_some_and(|terms_locale| terms_locale == locale)
+        })
+        .collect()
+}
+
 fn is_valid_locale(locale: &str) -> bool {
     if locale.is_empty() {
         return false;
@@ -0,0 +1,54 @@
+/// Prefix for ids which would otherwise not start with a letter.
+const ID_PREFIX: &str = "msg-";
+
+/// Converts text into a lowercase identifier of words separated by dashes, e.g. `press-any-key-to-continue`.
+///
+/// If `max_length` is not 0, the slug is shortened to at most that many characters, preferably at a word boundary.
+pub fn slugify(text: &str, max_length: usize) -> String {
+    let mut slug = String::new();
+    for char in text.chars() {
+        if char.is_ascii_alphanumeric() {
+            slug.push(char.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let mut slug = slug.trim_end_matches('-').to_owned();
+
+    if !slug.starts_with(|char: char| char.is_ascii_alphabetic()) {
+        slug.insert_str(0, ID_PREFIX);
+        slug = slug.trim_end_matches('-').to_owned();
+    }
+
+    if max_length > 0 && slug.len() > max_length {
+        // All characters are ASCII at this point, so byte indices are fine.
+        let shortened = &slug[..max_length];
+        slug = match shortened.rfind('-') {
+            Some(index) if index > max_length / 2 => shortened[..index].to_owned(),
+            _ => shortened.trim_end_matches('-').to_owned(),
+        };
+    }
+    slug
+}
+
+/// Returns an id derived from a stable hash of the text, e.g. `msg-1a2b3c4d`.
+pub fn content_hash(text: &str) -> String {
+    format!("{ID_PREFIX}{}", text_hash(text))
+}
+
+/// Returns a stable hash of the text as hexadecimal string, e.g. `1a2b3c4d`.
+pub fn text_hash(text: &str) -> String {
+    // 32-bit FNV-1a, which is stable across platforms and Rust versions (unlike DefaultHasher).
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in text.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    format!("{hash:08x}")
+}
+
+/// Appends a suffix derived from the text to an id, to disambiguate it from other messages with the same id.
+/// The suffix only depends on the text, so that it does not change when other texts are added or removed.
+pub fn disambiguate(id: &str, text: &str) -> String {
+    format!("{id}-{}", text_hash(text))
+}
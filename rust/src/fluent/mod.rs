@@ -4,10 +4,28 @@ mod extractor;
 pub use self::extractor::*;
 mod extractor_packed_scene;
 pub use self::extractor_packed_scene::*;
+mod extractor_gdscript;
+pub use self::extractor_gdscript::*;
+mod extractor_csharp;
+pub use self::extractor_csharp::*;
+mod extractor_resource;
+pub use self::extractor_resource::*;
+mod extractor_script;
+pub use self::extractor_script::*;
 mod generator;
 pub use self::generator::*;
+mod generator_config;
+pub use self::generator_config::*;
+mod generator_cache;
+pub use self::generator_cache::*;
+mod generator_dock;
+pub use self::generator_dock::*;
 mod importer;
 pub use self::importer::*;
+mod converter;
+pub use self::converter::*;
+mod xliff;
+pub use self::xliff::*;
 mod translation;
 pub use self::translation::*;
 mod export_plugin;
@@ -15,6 +33,8 @@ pub use self::export_plugin::*;
 mod strip_comments;
 pub use self::strip_comments::*;
 pub mod locale;
+pub mod message_id;
+pub mod pattern;
 #[allow(dead_code)]
 pub mod project_settings;
 mod editor_plugin;
@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+
+use fluent_syntax::{ast, parser::parse, serializer::serialize};
+
+/// Converts plain text into a [ast::Pattern] which serializes back to the same text.
+pub fn text_to_pattern(text: &str) -> ast::Pattern<String> {
+    elements_to_pattern(vec![ast::PatternElement::TextElement { value: text.to_owned() }])
+}
+
+/// Converts text elements containing plain text (and placeables in between) into a [ast::Pattern] which serializes back to the same text.
+///
+/// Everything that FTL would otherwise strip or interpret is wrapped in string literal placeables:
+/// braces, leading and trailing whitespace, lines consisting only of whitespace,
+/// and `[`, `*` or `.` at the start of a line (which would start a variant or attribute).
+pub fn elements_to_pattern(elements: Vec<ast::PatternElement<String>>) -> ast::Pattern<String> {
+    let tokens = elements
+        .into_iter()
+        .flat_map(|element| match element {
+            ast::PatternElement::TextElement { value } => value.chars().map(Token::Char).collect::<Vec<_>>(),
+            placeable => vec![Token::Placeable(placeable)],
+        })
+        .collect::<Vec<_>>();
+
+    let mut elements = vec![];
+    let Some(start) = tokens.iter().position(|token| !token.is_whitespace()) else {
+        // An empty value would not be parsed as a message at all.
+        elements.push(string_literal(escape_string_literal(&tokens_to_text(&tokens))));
+        return ast::Pattern { elements };
+    };
+    let end = tokens.iter().rposition(|token| !token.is_whitespace()).unwrap_or(start) + 1;
+
+    if start > 0 {
+        elements.push(string_literal(escape_string_literal(&tokens_to_text(&tokens[..start]))));
+    }
+
+    let core = &tokens[start..end];
+    let is_multiline = core.iter().any(|token| matches!(token, Token::Char('\n')));
+    let mut current = String::new();
+    for (index, line) in core.split(|token| matches!(token, Token::Char('\n'))).enumerate() {
+        if index > 0 {
+            // Each line must be its own text element for the serializer to indent it.
+            current.push('\n');
+            push_text(&mut elements, &mut current);
+        }
+        if line.is_empty() {
+            continue;
+        }
+        if line.iter().all(Token::is_whitespace) {
+            // Whitespace-only lines would be treated as blank lines.
+            elements.push(string_literal(escape_string_literal(&tokens_to_text(line))));
+            continue;
+        }
+
+        let content_start = line.iter().position(|token| !token.is_whitespace()).unwrap_or_default();
+        current.push_str(&tokens_to_text(&line[..content_start]));
+        let mut content = line[content_start..].iter();
+        // Multiline patterns start on a new line, so the first line is a block line as well.
+        if index > 0 || is_multiline {
+            if let Some(Token::Char(first @ ('[' | '*' | '.'))) = line.get(content_start) {
+                push_text(&mut elements, &mut current);
+                elements.push(string_literal(first.to_string()));
+                content.next();
+            }
+        }
+
+        for token in content {
+            match token {
+                Token::Char(char @ ('{' | '}')) => {
+                    // Braces would start or end a placeable, so wrap them in a string literal.
+                    push_text(&mut elements, &mut current);
+                    elements.push(string_literal(char.to_string()));
+                },
+                Token::Char(char) => current.push(*char),
+                Token::Placeable(placeable) => {
+                    push_text(&mut elements, &mut current);
+                    elements.push(placeable.clone());
+                },
+            }
+        }
+    }
+    push_text(&mut elements, &mut current);
+
+    if end < tokens.len() {
+        elements.push(string_literal(escape_string_literal(&tokens_to_text(&tokens[end..]))));
+    }
+
+    ast::Pattern { elements }
+}
+
+/// Replaces printf placeholders (`%s`, `%d`, ...) and [method String.format] placeholders (`{name}`, `{0}`) in the text of a pattern with variables.
+///
+/// printf placeholders become `$arg1`, `$arg2`, ... in order, numbered placeholders `{n}` become `$arg{n + 1}`
+/// and named placeholders keep their name. Returns the new pattern and the placeholder of each variable.
+///
+/// Texts mixing both kinds of placeholders are left unchanged, since e.g. `%s` and `{0}` would both become `$arg1`.
+pub fn convert_placeholders(pattern: &ast::Pattern<String>) -> (ast::Pattern<String>, BTreeMap<String, String>) {
+    // Unescape string literals and merge them with the surrounding text, so that placeholders like `{name}` are found.
+    let mut elements = vec![];
+    let mut text = String::new();
+    for element in &pattern.elements {
+        match (element, string_literal_text(element)) {
+            (ast::PatternElement::TextElement { value }, _) => text.push_str(value),
+            (_, Some(value)) => text.push_str(&value),
+            (placeable, None) => {
+                push_text(&mut elements, &mut text);
+                elements.push(placeable.clone());
+            },
+        }
+    }
+    push_text(&mut elements, &mut text);
+    let contains = |parse: fn(&str) -> bool| elements.iter().any(|element| match element {
+        ast::PatternElement::TextElement { value } => value.char_indices().any(|(index, _)| parse(&value[index..])),
+        _ => false,
+    });
+    let has_printf = contains(|text| parse_printf(text).is_some());
+    let has_format = contains(|text| parse_format(text).is_some());
+    if has_printf && has_format {
+        return (pattern.clone(), BTreeMap::new());
+    }
+
+    let mut variables = BTreeMap::new();
+    let mut printf_count = 0;
+    let mut result = vec![];
+    for element in elements {
+        let ast::PatternElement::TextElement { value } = element else {
+            result.push(element);
+            continue;
+        };
+
+        let mut current = String::new();
+        let mut rest = value.as_str();
+        while let Some(char) = rest.chars().next() {
+            let placeholder = match char {
+                '%' if rest.starts_with("%%") => {
+                    // Escaped percent sign, which is only unescaped when used with the % operator.
+                    current.push_str(if has_printf { "%" } else { "%%" });
+                    rest = &rest[2..];
+                    continue;
+                },
+                '%' => parse_printf(rest).map(|length| {
+                    printf_count += 1;
+                    (length, format!("arg{printf_count}"))
+                }),
+                '{' => parse_format(rest),
+                _ => None,
+            };
+
+            match placeholder {
+                Some((length, variable)) => {
+                    push_text(&mut result, &mut current);
+                    result.push(ast::PatternElement::Placeable {
+                        expression: ast::Expression::Inline(ast::InlineExpression::VariableReference {
+                            id: ast::Identifier { name: variable.clone() },
+                        }),
+                    });
+                    variables.entry(variable).or_insert_with(|| rest[..length].to_owned());
+                    rest = &rest[length..];
+                },
+                None => {
+                    current.push(char);
+                    rest = &rest[char.len_utf8()..];
+                },
+            }
+        }
+        push_text(&mut result, &mut current);
+    }
+
+    (elements_to_pattern(result), variables)
+}
+
+/// Returns the length of the printf placeholder at the start of the text, e.g. `%s` or `%-5.2f`.
+///
+/// The space flag is not supported, so that ordinary text like `100% sure` is not mistaken for a placeholder.
+fn parse_printf(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('%')?;
+    let rest = rest.trim_start_matches(['-', '+', '0']);
+    let rest = rest.strip_prefix('*').unwrap_or_else(|| rest.trim_start_matches(|char: char| char.is_ascii_digit()));
+    let rest = match rest.strip_prefix('.') {
+        Some(rest) => rest.strip_prefix('*').unwrap_or_else(|| rest.trim_start_matches(|char: char| char.is_ascii_digit())),
+        None => rest,
+    };
+    let rest = rest.strip_prefix(['s', 'c', 'd', 'i', 'o', 'x', 'X', 'f', 'v'])?;
+    Some(text.len() - rest.len())
+}
+
+/// Returns the length of the [method String.format] placeholder at the start of the text and its variable name.
+fn parse_format(text: &str) -> Option<(usize, String)> {
+    let (name, _) = text.strip_prefix('{')?.split_once('}')?;
+    let variable = if !name.is_empty() && name.chars().all(|char| char.is_ascii_digit()) {
+        format!("arg{}", name.parse::<usize>().ok()? + 1)
+    } else if name.starts_with(|char: char| char.is_ascii_alphabetic()) && name.chars().all(|char| char.is_ascii_alphanumeric() || char == '_') {
+        name.to_owned()
+    } else {
+        return None;
+    };
+    Some((name.len() + 2, variable))
+}
+
+/// Escapes text for use in a string literal, i.e. backslashes, quotes and control characters like line breaks.
+pub fn escape_string_literal(text: &str) -> String {
+    let mut result = String::new();
+    for char in text.chars() {
+        match char {
+            '\\' | '"' => {
+                result.push('\\');
+                result.push(char);
+            },
+            _ if char.is_control() => result.push_str(&format!("\\u{:04X}", char as u32)),
+            _ => result.push(char),
+        }
+    }
+    result
+}
+
+/// Creates a placeable containing a string literal. The value must already be escaped.
+pub fn string_literal(value: String) -> ast::PatternElement<String> {
+    ast::PatternElement::Placeable {
+        expression: ast::Expression::Inline(ast::InlineExpression::StringLiteral { value }),
+    }
+}
+
+/// Returns the unescaped value if the element is a string literal placeable, like `{ "{" }`.
+pub fn string_literal_text(element: &ast::PatternElement<String>) -> Option<String> {
+    let ast::PatternElement::Placeable {
+        expression: ast::Expression::Inline(ast::InlineExpression::StringLiteral { value }),
+    } = element else {
+        return None;
+    };
+
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some(escape @ ('u' | 'U')) => {
+                let length = if escape == 'u' { 4 } else { 6 };
+                let hex = chars.by_ref().take(length).collect::<String>();
+                let unescaped = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                result.push(unescaped.unwrap_or(char::REPLACEMENT_CHARACTER));
+            },
+            Some(other) => result.push(other),
+            None => {},
+        }
+    }
+    Some(result)
+}
+
+/// Serializes a single pattern element into FTL source, e.g. `{ $name }`.
+pub fn element_to_source(element: &ast::PatternElement<String>) -> String {
+    let ftl = ast::Resource {
+        body: vec![ast::Entry::Message(ast::Message {
+            id: ast::Identifier { name: "x".to_owned() },
+            value: Some(ast::Pattern { elements: vec![element.clone()] }),
+            attributes: Default::default(),
+            comment: None,
+        })],
+    };
+    let source = serialize(&ftl);
+    source.strip_prefix("x =").unwrap_or(&source).trim().to_owned()
+}
+
+/// Parses the FTL source of a single placeable, as returned by [element_to_source].
+pub fn source_to_element(source: &str) -> Option<ast::PatternElement<String>> {
+    let ftl = match parse(format!("x = {}\n", source)) {
+        Ok(ftl) => ftl,
+        Err(_) => return None,
+    };
+    let Some(ast::Entry::Message(message)) = ftl.body.into_iter().next() else {
+        return None;
+    };
+    let mut elements = message.value?.elements;
+    if elements.len() != 1 {
+        return None;
+    }
+    elements.pop()
+}
+
+fn push_text(elements: &mut Vec<ast::PatternElement<String>>, current: &mut String) {
+    if !current.is_empty() {
+        elements.push(ast::PatternElement::TextElement { value: std::mem::take(current) });
+    }
+}
+
+enum Token {
+    Char(char),
+    Placeable(ast::PatternElement<String>),
+}
+
+impl Token {
+    fn is_whitespace(&self) -> bool {
+        matches!(self, Token::Char(char) if char.is_whitespace())
+    }
+}
+
+/// Returns the characters of the tokens, which must not contain placeables.
+fn tokens_to_text(tokens: &[Token]) -> String {
+    tokens.iter().filter_map(|token| match token {
+        Token::Char(char) => Some(*char),
+        Token::Placeable(_) => None,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the text as message value, parses it again and returns the resulting text.
+    fn round_trip(text: &str) -> String {
+        let ftl = ast::Resource {
+            body: vec![ast::Entry::Message(ast::Message {
+                id: ast::Identifier { name: "x".to_owned() },
+                value: Some(text_to_pattern(text)),
+                attributes: vec![],
+                comment: None,
+            })],
+        };
+        let source = serialize(&ftl);
+        let ftl = parse(source.clone()).unwrap_or_else(|(_, errors)| panic!("{source:?} does not parse: {errors:?}"));
+        let Some(ast::Entry::Message(message)) = ftl.body.into_iter().next() else {
+            panic!("{source:?} does not contain a message");
+        };
+        message.value
+            .expect("message has no value")
+            .elements
+            .iter()
+            .map(|element| match element {
+                ast::PatternElement::TextElement { value } => value.clone(),
+                placeable => string_literal_text(placeable).expect("unexpected placeable"),
+            })
+            .collect()
+    }
+
+    fn assert_round_trip(text: &str) {
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn round_trips_plain_text() {
+        assert_round_trip("Start game");
+        assert_round_trip("First line\nSecond line");
+    }
+
+    #[test]
+    fn round_trips_braces() {
+        assert_round_trip("{");
+        assert_round_trip("Use {name} or { $var }}");
+    }
+
+    #[test]
+    fn round_trips_surrounding_whitespace() {
+        assert_round_trip("  padded  ");
+        assert_round_trip("\ttab");
+        assert_round_trip("trailing newline\n");
+        assert_round_trip("\nleading newline");
+    }
+
+    #[test]
+    fn round_trips_whitespace_only_lines() {
+        assert_round_trip("First\n   \nThird");
+        assert_round_trip("First\n\nThird");
+        assert_round_trip("First\n  indented\nThird");
+    }
+
+    #[test]
+    fn round_trips_special_line_starts() {
+        assert_round_trip("First\n[variant]");
+        assert_round_trip("First\n*default");
+        assert_round_trip("First\n.attribute");
+        assert_round_trip("[bracket]\nSecond");
+        assert_round_trip("[single line]");
+        assert_round_trip(".dot");
+    }
+
+    #[test]
+    fn round_trips_empty_text() {
+        assert_round_trip("");
+        assert_round_trip("   ");
+    }
+
+    #[test]
+    fn round_trips_control_characters() {
+        assert_round_trip("Bell\u{7}");
+        assert_round_trip("\u{1}start");
+        assert_round_trip("\r\n");
+        assert_round_trip("Quote \" and \\ backslash ");
+    }
+
+    #[test]
+    fn round_trips_percent_signs() {
+        assert_round_trip("100%%");
+        assert_round_trip("%% of %s");
+    }
+
+    fn convert(text: &str) -> (String, BTreeMap<String, String>) {
+        let (pattern, variables) = convert_placeholders(&text_to_pattern(text));
+        let source = pattern.elements.iter().map(|element| match element {
+            ast::PatternElement::TextElement { value } => value.clone(),
+            placeable => element_to_source(placeable),
+        }).collect();
+        (source, variables)
+    }
+
+    #[test]
+    fn converts_placeholders() {
+        let (source, variables) = convert("Score: %d of %s");
+        assert_eq!(source, "Score: { $arg1 } of { $arg2 }");
+        assert_eq!(variables, BTreeMap::from([("arg1".to_owned(), "%d".to_owned()), ("arg2".to_owned(), "%s".to_owned())]));
+
+        let (source, _) = convert("Hello {name}, {0}");
+        assert_eq!(source, "Hello { $name }, { $arg1 }");
+
+        let (source, _) = convert("%d%% done");
+        assert_eq!(source, "{ $arg1 }% done");
+    }
+
+    #[test]
+    fn keeps_percent_signs_in_text() {
+        for text in ["100% sure", "50% discount", "5% off"] {
+            let (source, variables) = convert(text);
+            assert_eq!(source, text);
+            assert!(variables.is_empty());
+        }
+    }
+
+    #[test]
+    fn keeps_mixed_placeholders() {
+        let (pattern, variables) = convert_placeholders(&text_to_pattern("%s and {0}"));
+        assert_eq!(pattern, text_to_pattern("%s and {0}"));
+        assert!(variables.is_empty());
+    }
+}
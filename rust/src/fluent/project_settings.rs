@@ -12,13 +12,47 @@ pub(crate) const PROJECT_SETTING_LOADER_LOCALE_BY_FILE_REGEX: &str = constcat!(P
 pub(crate) const PROJECT_SETTING_LOADER_PATTERN_BY_FOLDER_REGEX: &str = constcat!(PROJECT_SETTING_PREFIX, "loader/pattern_by_folder_regex");
 pub(crate) const PROJECT_SETTING_LOADER_PATTERN_BY_FILE_REGEX: &str = constcat!(PROJECT_SETTING_PREFIX, "loader/pattern_by_file_regex");
 pub(crate) const PROJECT_SETTING_LOADER_MESSAGE_PATTERN: &str = constcat!(PROJECT_SETTING_PREFIX, "loader/message_pattern");
+pub(crate) const PROJECT_SETTING_LOADER_TERMS_FILE_REGEX: &str = constcat!(PROJECT_SETTING_PREFIX, "loader/terms_file_regex");
 pub(crate) const PROJECT_SETTING_GENERATOR_LOCALES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/locales");
 pub(crate) const PROJECT_SETTING_GENERATOR_PATTERNS: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/file_patterns");
 pub(crate) const PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/invalid_message_handling");
+pub(crate) const PROJECT_SETTING_GENERATOR_STALE_MESSAGE_HANDLING: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/stale_message_handling");
+pub(crate) const PROJECT_SETTING_GENERATOR_RESOURCE_INCLUDE_PROPERTIES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/resource_include_properties");
+pub(crate) const PROJECT_SETTING_GENERATOR_RESOURCE_EXCLUDE_PROPERTIES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/resource_exclude_properties");
+pub(crate) const PROJECT_SETTING_GENERATOR_EXTRACTOR_SCRIPTS: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/extractor_scripts");
+pub(crate) const PROJECT_SETTING_GENERATOR_SOURCE_REFERENCES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/source_references");
+pub(crate) const PROJECT_SETTING_GENERATOR_ATTRIBUTE_PROPERTIES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/attribute_properties");
+pub(crate) const PROJECT_SETTING_GENERATOR_ID_STRATEGY: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/id_strategy");
+pub(crate) const PROJECT_SETTING_GENERATOR_ID_MAX_LENGTH: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/id_max_length");
+pub(crate) const PROJECT_SETTING_GENERATOR_ID_METADATA_KEY: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/id_metadata_key");
+pub(crate) const PROJECT_SETTING_GENERATOR_COMMENT_METADATA_KEY: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/comment_metadata_key");
+pub(crate) const PROJECT_SETTING_GENERATOR_USE_EDITOR_DESCRIPTION: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/use_editor_description");
+pub(crate) const PROJECT_SETTING_GENERATOR_SOURCE_LOCALE: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/source_locale");
+pub(crate) const PROJECT_SETTING_GENERATOR_TARGET_SEEDING: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/target_seeding");
+pub(crate) const PROJECT_SETTING_GENERATOR_CONVERT_PLACEHOLDERS: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/convert_placeholders");
+pub(crate) const PROJECT_SETTING_GENERATOR_USE_CACHE: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/use_cache");
+pub(crate) const PROJECT_SETTING_GENERATOR_EXCLUDE_PATTERNS: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/exclude_patterns");
+pub(crate) const PROJECT_SETTING_GENERATOR_REGENERATE_ON_SAVE: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/regenerate_on_save");
+pub(crate) const PROJECT_SETTING_GENERATOR_PROFILES: &str = constcat!(PROJECT_SETTING_PREFIX, "generator/profiles");
 
 pub(crate) const INVALID_MESSAGE_HANDLING_SKIP: i32 = 0;
 pub(crate) const INVALID_MESSAGE_HANDLING_CONVERT_TO_VALID: i32 = 1;
 
+pub(crate) const STALE_MESSAGE_HANDLING_LEAVE: i32 = 0;
+pub(crate) const STALE_MESSAGE_HANDLING_ANNOTATE: i32 = 1;
+pub(crate) const STALE_MESSAGE_HANDLING_MOVE_TO_GROUP: i32 = 2;
+pub(crate) const STALE_MESSAGE_HANDLING_DELETE: i32 = 3;
+
+pub(crate) const ID_STRATEGY_SOURCE_TEXT: i32 = 0;
+pub(crate) const ID_STRATEGY_SLUG: i32 = 1;
+pub(crate) const ID_STRATEGY_NODE_PATH: i32 = 2;
+pub(crate) const ID_STRATEGY_CONTENT_HASH: i32 = 3;
+
+pub(crate) const TARGET_SEEDING_SOURCE_TEXT: i32 = 0;
+pub(crate) const TARGET_SEEDING_EMPTY: i32 = 1;
+pub(crate) const TARGET_SEEDING_COMMENTED_SOURCE: i32 = 2;
+pub(crate) const TARGET_SEEDING_TODO_MARKER: i32 = 3;
+
 pub fn register() {
     register_setting(PROJECT_SETTING_UNICODE_ISOLATION.to_string(), false.to_variant());
     // Default to true for default builds (no args parameter), false for forked builds.
@@ -28,17 +62,37 @@ pub fn register() {
     register_setting(PROJECT_SETTING_LOADER_PATTERN_BY_FOLDER_REGEX.to_string(), "".to_variant());
     register_setting(PROJECT_SETTING_LOADER_PATTERN_BY_FILE_REGEX.to_string(), "".to_variant());
     register_setting(PROJECT_SETTING_LOADER_MESSAGE_PATTERN.to_string(), "".to_variant());
+    register_setting(PROJECT_SETTING_LOADER_TERMS_FILE_REGEX.to_string(), "".to_variant());
     register_setting_hint(PROJECT_SETTING_GENERATOR_LOCALES.to_string(), PackedStringArray::new().to_variant(), PropertyHint::NONE, format!("{}/{}:", VariantType::STRING.ord(), PropertyHint::LOCALE_ID.ord()));
     #[cfg(since_api = "4.4")]
     {
         // TODO: Use PropertyHint::DICTIONARY_TYPE once it is stable API.
         register_setting_hint(PROJECT_SETTING_GENERATOR_PATTERNS.to_string(), Dictionary::new().to_variant(), PropertyHint::from(38), "String;String".into());
+        register_setting_hint(PROJECT_SETTING_GENERATOR_ATTRIBUTE_PROPERTIES.to_string(), Dictionary::new().to_variant(), PropertyHint::from(38), "String;String".into());
     }
     #[cfg(before_api = "4.4")]
     {
         register_setting(PROJECT_SETTING_GENERATOR_PATTERNS.to_string(), Dictionary::new().to_variant());
+        register_setting(PROJECT_SETTING_GENERATOR_ATTRIBUTE_PROPERTIES.to_string(), Dictionary::new().to_variant());
     }
     register_setting_hint(PROJECT_SETTING_GENERATOR_INVALID_MESSAGE_HANDLING.to_string(), 0.to_variant(), PropertyHint::ENUM, "Skip message,Convert to valid".into());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_STALE_MESSAGE_HANDLING.to_string(), STALE_MESSAGE_HANDLING_LEAVE.to_variant(), PropertyHint::ENUM, "Leave,Annotate,Move to obsolete group,Delete".into());
+    register_setting(PROJECT_SETTING_GENERATOR_RESOURCE_INCLUDE_PROPERTIES.to_string(), PackedStringArray::from(&["^.+_name$", "^text$", "^.+_text$", "^title$", "^description$"].map(GString::from)).to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_RESOURCE_EXCLUDE_PROPERTIES.to_string(), PackedStringArray::from(&["^resource_.+$"].map(GString::from)).to_variant());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_EXTRACTOR_SCRIPTS.to_string(), PackedStringArray::new().to_variant(), PropertyHint::NONE, format!("{}/{}:*.gd,*.cs", VariantType::STRING.ord(), PropertyHint::FILE.ord()));
+    register_setting(PROJECT_SETTING_GENERATOR_SOURCE_REFERENCES.to_string(), true.to_variant());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_ID_STRATEGY.to_string(), ID_STRATEGY_SOURCE_TEXT.to_variant(), PropertyHint::ENUM, "Source text,Slug,Node path,Content hash".into());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_ID_MAX_LENGTH.to_string(), 40.to_variant(), PropertyHint::RANGE, "0,256,1,or_greater".into());
+    register_setting(PROJECT_SETTING_GENERATOR_ID_METADATA_KEY.to_string(), "fluent_id".to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_COMMENT_METADATA_KEY.to_string(), "fluent_comment".to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_USE_EDITOR_DESCRIPTION.to_string(), false.to_variant());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_SOURCE_LOCALE.to_string(), "".to_variant(), PropertyHint::LOCALE_ID, String::new());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_TARGET_SEEDING.to_string(), TARGET_SEEDING_SOURCE_TEXT.to_variant(), PropertyHint::ENUM, "Source text,Empty,Commented source text,TODO marker".into());
+    register_setting(PROJECT_SETTING_GENERATOR_CONVERT_PLACEHOLDERS.to_string(), false.to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_USE_CACHE.to_string(), true.to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_REGENERATE_ON_SAVE.to_string(), false.to_variant());
+    register_setting(PROJECT_SETTING_GENERATOR_EXCLUDE_PATTERNS.to_string(), PackedStringArray::from(&["^res://addons/"].map(GString::from)).to_variant());
+    register_setting_hint(PROJECT_SETTING_GENERATOR_PROFILES.to_string(), PackedStringArray::new().to_variant(), PropertyHint::NONE, format!("{}/{}:*.tres,*.res", VariantType::STRING.ord(), PropertyHint::FILE.ord()));
 }
 
 fn register_setting(name: String, value: Variant) {
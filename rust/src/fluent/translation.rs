@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use fluent::types::FluentNumber;
 use fluent::{FluentArgs, FluentBundle, FluentError, FluentResource, FluentValue};
+use fluent_syntax::{ast, parser::parse, serializer::serialize};
 use godot::prelude::*;
 use godot::classes::{ITranslation, ProjectSettings, RegEx, Translation};
 use godot::global::{str_to_var, var_to_str};
@@ -281,6 +282,45 @@ impl TranslationFluent {
     /// Changing these Project Settings after this call will not update already existing [TranslationFluent] resources.
     #[func]
     pub fn append_from_text(&mut self, text: String) -> GdErr {
+        match Self::parse_resource(text) {
+            Ok(res) => self.append_resource(res),
+            Err(err) => err,
+        }
+    }
+
+    /// Add only the terms of a Fluent Translation List (FTL) text to this translation, ignoring all of its messages.
+    /// This method is automatically called for shared terms files when the add-on creates a [TranslationFluent] resource for you.
+    ///
+    /// Terms which are already defined in this translation take precedence and are not overridden.
+    ///
+    /// Returns an [enum Error] value whether the data was successfully added.
+    #[func]
+    pub fn append_terms_from_text(&mut self, text: String) -> GdErr {
+        match Self::parse_terms_resource(text) {
+            Ok(res) => self.append_terms_resource(res),
+            Err(err) => err,
+        }
+    }
+
+    /// Parses a FTL text. Does not access the engine, so it is safe to call from any thread.
+    pub(crate) fn parse_resource(text: String) -> Result<FluentResource, GdErr> {
+        FluentResource::try_new(text).map_err(|_| {
+            // TODO: I could give more parser error details here, and probably should? :)
+            GdErr::ERR_PARSE_ERROR
+        })
+    }
+
+    /// Parses a FTL text, only keeping its terms. Does not access the engine, so it is safe to call from any thread.
+    pub(crate) fn parse_terms_resource(text: String) -> Result<FluentResource, GdErr> {
+        let mut ftl = match parse(text) {
+            Ok(ftl) => ftl,
+            Err((ftl, _err)) => ftl,
+        };
+        ftl.body.retain(|entry| matches!(entry, ast::Entry::Term(_)));
+        Self::parse_resource(serialize(&ftl))
+    }
+
+    pub(crate) fn append_resource(&mut self, res: FluentResource) -> GdErr {
         let bundle = match &mut self.bundle {
             Some(bundle) => bundle,
             None => &mut {
@@ -295,14 +335,41 @@ impl TranslationFluent {
             },
         };
 
-        let Ok(res) = FluentResource::try_new(text) else {
-            // TODO: I could give more parser error details here, and probably should? :)
-            return GdErr::ERR_PARSE_ERROR;
+        match bundle.add_resource(res) {
+            Ok(_) => GdErr::OK,
+            Err(errors) => Self::map_fluent_error_list(&errors),
+        }
+    }
+
+    pub(crate) fn append_terms_resource(&mut self, res: FluentResource) -> GdErr {
+        let bundle = match &mut self.bundle {
+            Some(bundle) => bundle,
+            None => &mut {
+                let bundle = self.create_bundle();
+                match bundle {
+                    Ok(bundle) => {
+                        self.bundle = Some(bundle);
+                        self.bundle.as_mut().unwrap()
+                    },
+                    Err(err) => return err
+                }
+            },
         };
 
         match bundle.add_resource(res) {
             Ok(_) => GdErr::OK,
-            Err(errors) => Self::map_fluent_error_list(&errors),
+            Err(errors) => {
+                // Locally defined terms win over shared ones.
+                let errors = errors
+                    .into_iter()
+                    .filter(|error| !matches!(error, FluentError::Overriding { .. }))
+                    .collect::<Vec<_>>();
+                if errors.is_empty() {
+                    GdErr::OK
+                } else {
+                    Self::map_fluent_error_list(&errors)
+                }
+            },
         }
     }
 
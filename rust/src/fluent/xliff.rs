@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use fluent_syntax::ast;
+use fluent_syntax::parser::parse;
+use fluent_syntax::serializer::serialize;
+use godot::classes::FileAccess;
+use godot::global::error_string;
+use godot::global::Error as GdErr;
+use godot::prelude::*;
+
+use crate::utils::create_file_for_write;
+
+use super::locale::compute_locale;
+use super::pattern::{element_to_source, elements_to_pattern, source_to_element, string_literal_text};
+use super::FluentGenerator;
+
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+/// Exchanges Fluent Translation List (FTL) files with translation tools using the XLIFF 2.0 format.
+///
+/// Every message, term and attribute becomes its own translation unit, identified by `message`, `-term` or `message.attribute`.
+/// Comments are exported as notes (except those the [FluentGenerator] writes for itself), and placeables like `{ $name }` are protected as inline codes so that they can not be altered by translators.
+#[derive(GodotClass)]
+#[class(init)]
+pub struct FluentXliff {}
+
+/// Mixed content of a source or target element.
+#[derive(Clone)]
+enum XliffContent {
+    Text(String),
+    Placeholder(String),
+}
+
+#[derive(Default)]
+struct XliffUnit {
+    id: String,
+    data: HashMap<String, String>,
+    target: Option<Vec<XliffContent>>,
+    complete: bool,
+}
+
+enum XmlEvent {
+    Start { name: String, attributes: HashMap<String, String>, empty: bool },
+    End { name: String },
+    Text(String),
+}
+
+#[godot_api]
+impl FluentXliff {
+    /// Export the FTL file at [param source_path] to a XLIFF 2.0 file at [param xliff_path].
+    ///
+    /// The locales are deduced from [param source_path] and [param target_path] like the loader does.
+    /// If the FTL file at [param target_path] exists, its messages are exported as existing translations.
+    #[func]
+    pub fn export_xliff(&self, source_path: GString, target_path: GString, xliff_path: GString) -> GdErr {
+        let Some(source_locale) = compute_locale(&PathBuf::from(source_path.to_string())) else {
+            godot_error!("Unable to determine locale of {}.", source_path);
+            return GdErr::ERR_INVALID_PARAMETER;
+        };
+        let target_locale = compute_locale(&PathBuf::from(target_path.to_string()));
+
+        let source = match Self::read_ftl(&source_path) {
+            Ok(source) => source,
+            Err(err) => return err,
+        };
+        let target = if FileAccess::file_exists(&target_path) {
+            match Self::read_ftl(&target_path) {
+                Ok(target) => Self::get_patterns(&target),
+                Err(err) => return err,
+            }
+        } else {
+            HashMap::new()
+        };
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<xliff xmlns=\"{}\" version=\"2.0\" srcLang=\"{}\"", XLIFF_NAMESPACE, escape_xml(&source_locale)));
+        if let Some(target_locale) = &target_locale {
+            xml.push_str(&format!(" trgLang=\"{}\"", escape_xml(target_locale)));
+        }
+        xml.push_str(">\n");
+        xml.push_str(&format!("  <file id=\"f1\" original=\"{}\">\n", escape_xml(&source_path.to_string())));
+
+        for entry in &source.body {
+            let (id, value, attributes, comment) = match entry {
+                ast::Entry::Message(message) => (message.id.name.clone(), message.value.as_ref(), &message.attributes, &message.comment),
+                ast::Entry::Term(term) => (format!("-{}", term.id.name), Some(&term.value), &term.attributes, &term.comment),
+                _ => continue,
+            };
+            let notes = comment
+                .as_ref()
+                .map(|comment| comment.content.iter().filter(|line| !FluentGenerator::is_generated_comment_line(line)).cloned().collect())
+                .unwrap_or_default();
+
+            if let Some(value) = value {
+                Self::write_unit(&mut xml, &id, value, target.get(&id), &notes);
+            }
+            for attribute in attributes {
+                let attribute_id = format!("{}.{}", id, attribute.id.name);
+                Self::write_unit(&mut xml, &attribute_id, &attribute.value, target.get(&attribute_id), &notes);
+            }
+        }
+
+        xml.push_str("  </file>\n");
+        xml.push_str("</xliff>\n");
+
+        match create_file_for_write(&xliff_path) {
+            Ok(mut fa) => {
+                fa.store_string(&xml);
+                GdErr::OK
+            },
+            Err(err) => {
+                godot_error!("Unable to open file {} for writing: {}", xliff_path, error_string(err.ord() as i64));
+                err
+            },
+        }
+    }
+
+    /// Import the translations of a XLIFF 2.0 file at [param xliff_path] into the FTL file at [param target_path].
+    ///
+    /// Only messages, terms and attributes which do not exist in the FTL file yet are added. Existing entries are never modified.
+    /// Units without a complete translation are skipped, as are units whose id is not a valid Fluent identifier (with a warning).
+    #[func]
+    pub fn import_xliff(&self, xliff_path: GString, target_path: GString) -> GdErr {
+        let text = FileAccess::get_file_as_string(&xliff_path);
+        let err = FileAccess::get_open_error();
+        if err != GdErr::OK {
+            godot_error!("Unable to read {}: {}", xliff_path, error_string(err.ord() as i64));
+            return err;
+        }
+
+        let units = match parse_xml(&text.to_string()) {
+            Ok(events) => Self::read_units(events),
+            Err(err) => {
+                godot_error!("Error parsing {}: {}", xliff_path, err);
+                return GdErr::ERR_PARSE_ERROR;
+            },
+        };
+
+        let mut ftl = if FileAccess::file_exists(&target_path) {
+            match Self::read_ftl(&target_path) {
+                Ok(ftl) => ftl,
+                Err(err) => return err,
+            }
+        } else {
+            ast::Resource { body: vec![] }
+        };
+
+        for unit in units {
+            let Some(target) = unit.target.filter(|_| unit.complete) else {
+                continue;
+            };
+            let Some(pattern) = Self::content_to_pattern(&target, &unit.data) else {
+                godot_warn!("Unit {} of {} contains invalid inline codes, skipping.", unit.id, xliff_path);
+                continue;
+            };
+            if !Self::merge_unit(&mut ftl, &unit.id, pattern) {
+                godot_warn!("Unit {} of {} has an invalid identifier, skipping.", unit.id, xliff_path);
+            }
+        }
+
+        let ftl = serialize(&ftl);
+        match create_file_for_write(&target_path) {
+            Ok(mut fa) => {
+                fa.store_string(&ftl);
+                GdErr::OK
+            },
+            Err(err) => {
+                godot_error!("Unable to open file {} for writing: {}", target_path, error_string(err.ord() as i64));
+                err
+            },
+        }
+    }
+
+    fn read_ftl(path: &GString) -> Result<ast::Resource<String>, GdErr> {
+        let text = FileAccess::get_file_as_string(path);
+        let err = FileAccess::get_open_error();
+        if err != GdErr::OK {
+            godot_error!("Unable to read {}: {}", path, error_string(err.ord() as i64));
+            return Err(err);
+        }
+
+        match parse(text.to_string()) {
+            Ok(ftl) => Ok(ftl),
+            Err((ftl, err)) => {
+                godot_warn!("Error parsing {}: {:?}", path, err);
+                Ok(ftl)
+            },
+        }
+    }
+
+    /// Returns all patterns of a FTL file by their unit id.
+    fn get_patterns(ftl: &ast::Resource<String>) -> HashMap<String, ast::Pattern<String>> {
+        let mut patterns = HashMap::new();
+        for entry in &ftl.body {
+            let (id, value, attributes) = match entry {
+                ast::Entry::Message(message) => (message.id.name.clone(), message.value.as_ref(), &message.attributes),
+                ast::Entry::Term(term) => (format!("-{}", term.id.name), Some(&term.value), &term.attributes),
+                _ => continue,
+            };
+            if let Some(value) = value {
+                patterns.insert(id.clone(), value.clone());
+            }
+            for attribute in attributes {
+                patterns.insert(format!("{}.{}", id, attribute.id.name), attribute.value.clone());
+            }
+        }
+        patterns
+    }
+
+    fn write_unit(xml: &mut String, id: &str, source: &ast::Pattern<String>, target: Option<&ast::Pattern<String>>, notes: &[String]) {
+        // Placeables of source and target share the same original data.
+        let mut data = Vec::<String>::new();
+        let source = Self::pattern_to_xml(source, &mut data);
+        let target = target.map(|target| Self::pattern_to_xml(target, &mut data));
+
+        xml.push_str(&format!("    <unit id=\"{}\">\n", escape_xml(id)));
+        if !notes.is_empty() {
+            xml.push_str("      <notes>\n");
+            for note in notes {
+                xml.push_str(&format!("        <note>{}</note>\n", escape_xml(note)));
+            }
+            xml.push_str("      </notes>\n");
+        }
+        if !data.is_empty() {
+            xml.push_str("      <originalData>\n");
+            for (index, content) in data.iter().enumerate() {
+                xml.push_str(&format!("        <data id=\"d{}\">{}</data>\n", index + 1, escape_xml(content)));
+            }
+            xml.push_str("      </originalData>\n");
+        }
+
+        let state = if target.is_some() { "translated" } else { "initial" };
+        xml.push_str(&format!("      <segment state=\"{}\">\n", state));
+        xml.push_str(&format!("        <source>{}</source>\n", source));
+        if let Some(target) = target {
+            xml.push_str(&format!("        <target>{}</target>\n", target));
+        }
+        xml.push_str("      </segment>\n");
+        xml.push_str("    </unit>\n");
+    }
+
+    fn pattern_to_xml(pattern: &ast::Pattern<String>, data: &mut Vec<String>) -> String {
+        let mut xml = String::new();
+        let mut placeholder_index = 0;
+        for element in &pattern.elements {
+            if let ast::PatternElement::TextElement { value } = element {
+                xml.push_str(&escape_xml_content(value));
+                continue;
+            }
+            if let Some(text) = string_literal_text(element) {
+                // Escaped characters are regular text for translators.
+                xml.push_str(&escape_xml_content(&text));
+                continue;
+            }
+
+            let source = element_to_source(element);
+            let data_index = match data.iter().position(|existing| *existing == source) {
+                Some(data_index) => data_index,
+                None => {
+                    data.push(source);
+                    data.len() - 1
+                },
+            };
+            placeholder_index += 1;
+            xml.push_str(&format!("<ph id=\"{}\" dataRef=\"d{}\"/>", placeholder_index, data_index + 1));
+        }
+        xml
+    }
+
+    fn content_to_pattern(content: &[XliffContent], data: &HashMap<String, String>) -> Option<ast::Pattern<String>> {
+        let mut elements = vec![];
+        for content in content {
+            match content {
+                XliffContent::Text(value) => elements.push(ast::PatternElement::TextElement { value: value.clone() }),
+                XliffContent::Placeholder(data_ref) => elements.push(source_to_element(data.get(data_ref)?)?),
+            }
+        }
+        Some(elements_to_pattern(elements))
+    }
+
+    /// Adds the pattern to the FTL if it does not exist yet. Returns false if the unit id is invalid.
+    fn merge_unit(ftl: &mut ast::Resource<String>, unit_id: &str, pattern: ast::Pattern<String>) -> bool {
+        let (id, attribute) = match unit_id.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (unit_id, None),
+        };
+        let (is_term, id) = match id.strip_prefix('-') {
+            Some(term_id) => (true, term_id),
+            None => (false, id),
+        };
+        let is_valid = |name: &str| !name.is_empty() && FluentGenerator::make_safe_identifier(&name.to_owned()) == name;
+        if !is_valid(id) || attribute.is_some_and(|attribute| !is_valid(attribute)) {
+            return false;
+        }
+
+        let existing = ftl.body.iter_mut().find_map(|entry| match entry {
+            ast::Entry::Message(message) if !is_term && message.id.name == id => Some((Some(&mut message.value), &mut message.attributes)),
+            ast::Entry::Term(term) if is_term && term.id.name == id => Some((None, &mut term.attributes)),
+            _ => None,
+        });
+
+        match (existing, attribute) {
+            (Some((_, attributes)), Some(attribute)) => {
+                if !attributes.iter().any(|existing| existing.id.name == attribute) {
+                    attributes.push(ast::Attribute {
+                        id: ast::Identifier { name: attribute.to_owned() },
+                        value: pattern,
+                    });
+                }
+            },
+            (Some((Some(value), _)), None) => {
+                if value.is_none() {
+                    *value = Some(pattern);
+                }
+            },
+            (Some((None, _)), None) => {
+                // Terms always have a value already.
+            },
+            (None, _) if is_term => {
+                if attribute.is_some() {
+                    // A term can not be created from an attribute alone, since it requires a value.
+                    return true;
+                }
+                ftl.body.push(ast::Entry::Term(ast::Term {
+                    id: ast::Identifier { name: id.to_owned() },
+                    value: pattern,
+                    attributes: Default::default(),
+                    comment: None,
+                }));
+            },
+            (None, attribute) => {
+                let (value, attributes) = match attribute {
+                    Some(attribute) => (None, vec![ast::Attribute {
+                        id: ast::Identifier { name: attribute.to_owned() },
+                        value: pattern,
+                    }]),
+                    None => (Some(pattern), vec![]),
+                };
+                ftl.body.push(ast::Entry::Message(ast::Message {
+                    id: ast::Identifier { name: id.to_owned() },
+                    value,
+                    attributes,
+                    comment: None,
+                }));
+            },
+        }
+        true
+    }
+
+    fn read_units(events: Vec<XmlEvent>) -> Vec<XliffUnit> {
+        let mut units = vec![];
+        let mut unit: Option<XliffUnit> = None;
+        let mut data_id: Option<String> = None;
+        // Content of the current segment or ignorable.
+        let mut source: Option<Vec<XliffContent>> = None;
+        let mut target: Option<Vec<XliffContent>> = None;
+        let mut in_source = false;
+        let mut in_target = false;
+        // Targets of `initial` segments are usually copies of the source, which must not be imported as translations.
+        let mut is_initial = false;
+
+        for event in events {
+            match event {
+                XmlEvent::Start { name, mut attributes, empty } => match name.as_str() {
+                    "unit" => {
+                        unit = Some(XliffUnit {
+                            id: attributes.remove("id").unwrap_or_default(),
+                            target: Some(vec![]),
+                            complete: true,
+                            ..Default::default()
+                        });
+                    },
+                    "data" if !empty => data_id = attributes.remove("id"),
+                    "segment" | "ignorable" => {
+                        source = None;
+                        target = None;
+                        is_initial = name == "segment" && attributes.get("state").is_some_and(|state| state == "initial");
+                    },
+                    "source" => {
+                        source = Some(vec![]);
+                        in_source = true;
+                    },
+                    "target" => {
+                        target = Some(vec![]);
+                        in_target = true;
+                    },
+                    "cp" => {
+                        // Code point of a character which is not allowed in XML.
+                        let Some(char) = attributes.get("hex").and_then(|hex| u32::from_str_radix(hex, 16).ok()).and_then(char::from_u32) else {
+                            continue;
+                        };
+                        let current = if in_target { &mut target } else if in_source { &mut source } else { continue };
+                        if let Some(current) = current {
+                            current.push(XliffContent::Text(char.to_string()));
+                        }
+                    },
+                    "ph" => {
+                        let content = XliffContent::Placeholder(attributes.remove("dataRef").unwrap_or_default());
+                        let current = if in_target { &mut target } else if in_source { &mut source } else { continue };
+                        if let Some(current) = current {
+                            current.push(content);
+                        }
+                    },
+                    _ => {},
+                },
+                XmlEvent::End { name } => match name.as_str() {
+                    "unit" => units.extend(unit.take()),
+                    "data" => data_id = None,
+                    "source" => in_source = false,
+                    "target" => in_target = false,
+                    "segment" | "ignorable" => {
+                        let Some(unit) = &mut unit else {
+                            continue;
+                        };
+                        // Ignorables may omit their target, since they are not translated.
+                        let content = if name == "ignorable" { target.take().or(source.take()) } else { target.take() };
+                        let content = content.filter(|_| !is_initial);
+                        match (content, &mut unit.target) {
+                            (Some(mut content), Some(unit_target)) => unit_target.append(&mut content),
+                            _ => unit.complete = false,
+                        }
+                    },
+                    _ => {},
+                },
+                XmlEvent::Text(text) => {
+                    if let (Some(unit), Some(data_id)) = (&mut unit, &data_id) {
+                        unit.data.entry(data_id.clone()).or_default().push_str(&text);
+                        continue;
+                    }
+                    let current = if in_target { &mut target } else if in_source { &mut source } else { continue };
+                    if let Some(current) = current {
+                        current.push(XliffContent::Text(text));
+                    }
+                },
+            }
+        }
+        units
+    }
+}
+
+/// Whether the character may appear in an XML 1.0 document, which excludes most control characters.
+fn is_xml_char(char: char) -> bool {
+    !matches!(char, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+}
+
+/// Escapes text for attribute values and notes, removing characters which are not allowed in XML.
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .filter(|char| is_xml_char(*char))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes text of a source or target, encoding characters which are not allowed in XML as `<cp>` elements.
+fn escape_xml_content(text: &str) -> String {
+    let mut xml = String::new();
+    let mut rest = text;
+    while let Some(index) = rest.find(|char: char| !is_xml_char(char)) {
+        let char = rest[index..].chars().next().unwrap();
+        xml.push_str(&escape_xml(&rest[..index]));
+        xml.push_str(&format!("<cp hex=\"{:04X}\"/>", char as u32));
+        rest = &rest[index + char.len_utf8()..];
+    }
+    xml.push_str(&escape_xml(rest));
+    xml
+}
+
+fn unescape_xml(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let unescaped = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match unescaped {
+            Some(char) => {
+                result.push(char);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Minimal XML reader for XLIFF files.
+/// Godot's XMLParser is not used, since it drops short text nodes which only consist of whitespace.
+fn parse_xml(text: &str) -> Result<Vec<XmlEvent>, String> {
+    let mut events = vec![];
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("Unterminated comment")?;
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or("Unterminated CDATA section")?;
+            events.push(XmlEvent::Text(after[..end].to_owned()));
+            rest = &after[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest.find('>').ok_or("Unterminated declaration")?;
+            rest = &rest[end + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or("Unterminated end tag")?;
+            events.push(XmlEvent::End { name: local_name(after[..end].trim()) });
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            // Find the end of the tag, ignoring any '>' inside of attribute values.
+            let mut quote = None;
+            let end = after
+                .char_indices()
+                .find(|(_, char)| {
+                    match quote {
+                        Some(open) if open == *char => quote = None,
+                        Some(_) => {},
+                        None if *char == '"' || *char == '\'' => quote = Some(*char),
+                        None => return *char == '>',
+                    }
+                    false
+                })
+                .map(|(index, _)| index)
+                .ok_or("Unterminated start tag")?;
+
+            let tag = &after[..end];
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let (name, attributes) = parse_tag(tag)?;
+            events.push(XmlEvent::Start { name: name.clone(), attributes, empty });
+            if empty {
+                events.push(XmlEvent::End { name });
+            }
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            events.push(XmlEvent::Text(unescape_xml(&rest[..end])));
+            rest = &rest[end..];
+        }
+    }
+    Ok(events)
+}
+
+fn parse_tag(tag: &str) -> Result<(String, HashMap<String, String>), String> {
+    let tag = tag.trim();
+    let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let mut attributes = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after) = rest.split_once('=').ok_or_else(|| format!("Invalid attribute in tag {}", name))?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|char| *char == '"' || *char == '\'').ok_or_else(|| format!("Unquoted attribute in tag {}", name))?;
+        let after = &after[1..];
+        let end = after.find(quote).ok_or_else(|| format!("Unterminated attribute in tag {}", name))?;
+        attributes.insert(local_name(key.trim()), unescape_xml(&after[..end]));
+        rest = &after[end + 1..];
+    }
+    Ok((local_name(name), attributes))
+}
+
+/// Strips the namespace prefix of a tag or attribute name.
+fn local_name(name: &str) -> String {
+    name.rsplit_once(':').map_or(name, |(_, name)| name).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_control_characters() {
+        assert_eq!(escape_xml_content("a\u{1}<b>\tc"), "a<cp hex=\"0001\"/>&lt;b&gt;\tc");
+        assert_eq!(escape_xml("a\u{1}\"b\"\n"), "a&quot;b&quot;\n");
+    }
+
+    #[test]
+    fn reads_encoded_control_characters() {
+        let xml = concat!(
+            "<unit id=\"greeting\"><segment state=\"translated\">",
+            "<source>Hi</source><target>a<cp hex=\"0001\"/>b</target>",
+            "</segment></unit>",
+        );
+        let units = FluentXliff::read_units(parse_xml(xml).unwrap());
+        assert_eq!(units.len(), 1);
+        assert!(units[0].complete);
+        let target: String = units[0].target.iter().flatten().map(|content| match content {
+            XliffContent::Text(text) => text.as_str(),
+            XliffContent::Placeholder(_) => "",
+        }).collect();
+        assert_eq!(target, "a\u{1}b");
+    }
+}
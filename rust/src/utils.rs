@@ -7,7 +7,10 @@ use godot::global::error_string;
 use godot::prelude::*;
 use godot::global::Error as GdErr;
 
-pub fn get_files_recursive(path: GString) -> Vec<GString> {
+/// Returns the full path of each file in the folder and its subfolders.
+///
+/// Like the editor's file system, skips the `.godot` folder and folders containing a `.gdignore` file.
+pub fn get_file_paths_recursive(path: GString) -> Vec<GString> {
     let da = DirAccess::open(&path);
     if da.is_none() || DirAccess::get_open_error() != GdErr::OK {
         godot_error!("Unable to recurse through folder {}: {}", path, error_string(DirAccess::get_open_error().ord() as i64));
@@ -21,21 +24,31 @@ pub fn get_files_recursive(path: GString) -> Vec<GString> {
     let mut files = vec![];
     let mut file_name = da.get_next();
     while !file_name.is_empty() {
+        let child_path: GString = path_join(&path, &file_name).into();
         if da.current_is_dir() {
-            if &file_name.to_string() != ".godot" {
-                // TODO: use GString.path_join() once available (upstream issue)
-                let child_path = format!("{}/{}", path, file_name.clone()).into();
-                let mut recurse = get_files_recursive(child_path);
+            let is_ignored = FileAccess::file_exists(path_join(&child_path, &".gdignore".into()).as_str());
+            if &file_name.to_string() != ".godot" && !is_ignored {
+                let mut recurse = get_file_paths_recursive(child_path);
                 files.append(&mut recurse);
             }
         } else {
-            files.push(file_name);
+            files.push(child_path);
         }
         file_name = da.get_next();
     }
     files
 }
 
+fn path_join(base: &GString, file: &GString) -> String {
+    // TODO: use GString.path_join() once available (upstream issue)
+    let base = base.to_string();
+    if base.ends_with('/') {
+        format!("{}{}", base, file)
+    } else {
+        format!("{}/{}", base, file)
+    }
+}
+
 pub fn create_or_open_file_for_read_write(path: &GString) -> Result<Gd<FileAccess>, GdErr> {
     let dir_only = match PathBuf::from(path.clone().to_string()).parent() {
         Some(dir) => dir.to_str().unwrap_or_default().into(),
@@ -59,6 +72,23 @@ pub fn create_or_open_file_for_read_write(path: &GString) -> Result<Gd<FileAcces
     Ok(fa.unwrap())
 }
 
+pub fn create_file_for_write(path: &GString) -> Result<Gd<FileAccess>, GdErr> {
+    let dir_only = match PathBuf::from(path.clone().to_string()).parent() {
+        Some(dir) => dir.to_str().unwrap_or_default().into(),
+        None => String::new(),
+    };
+    let dir_err = DirAccess::make_dir_recursive_absolute(&dir_only);
+    if dir_err != GdErr::OK {
+        return Err(dir_err);
+    }
+
+    let fa = FileAccess::open(path, ModeFlags::WRITE);
+    if fa.is_none() || FileAccess::get_open_error() != GdErr::OK {
+        return Err(FileAccess::get_open_error());
+    }
+    Ok(fa.unwrap())
+}
+
 pub fn get_single_regex_match<T: Display>(regex_match: Gd<RegExMatch>, err_source: T) -> GString {
     // Ensure there is only one capture group.
     if regex_match.get_group_count() > 1 {